@@ -1,6 +1,23 @@
+// Anchor 0.31's #[program] codegen still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        default_account_state::DefaultAccountState, BaseStateWithExtensions, ExtensionType,
+        StateWithExtensions,
+    },
+    state::AccountState,
+};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 
 declare_id!("FtQbMDA7w8a9icfbMkuTxxQ695Wp9e6RQFSGVjmYQgz3");
 
@@ -20,14 +37,54 @@ pub struct BettingRound {
     pub fight_end_time: i64,
     pub initial_hp: u32,
     pub current_hp: u32,
-    pub prize_pool_amount: u64,  // CHANGED: Fixed prize pool from treasury
+    pub prize_pool_amount: u64, // CHANGED: Fixed prize pool from treasury
+    pub token_decimals: u8,
     pub phase: GamePhase,
-    pub total_death_bets: u64,  // CHANGED: Now just counts number of death bets
-    pub total_survival_bets: u64,  // CHANGED: Now just counts number of survival bets
+    pub total_death_bets: u64, // CHANGED: Now just counts number of death bets
+    pub total_survival_bets: u64, // CHANGED: Now just counts number of survival bets
     pub total_bets_count: u64,
     pub boss_defeated: bool,
     pub payouts_processed: bool,
-    pub escrow_bump: u8,
+    pub escrow_bump: u8, // Informational only; signing re-derives the bump from the seeds
+    pub config: RoundConfig,
+    pub claim_deadline: i64, // 0 = winners may claim indefinitely
+    pub total_claimed_count: u64,
+    pub total_paid_out: u64,
+    pub unclaimed_redistributed: bool,
+    pub bonus_per_winner: u64,
+    pub bets_merkle_root: [u8; 32], // Operator-attested commitment to every bet, set at fight start
+    pub bets_frozen: bool,          // Blocks new bets only; existing bets are untouched
+    pub outcome_attestation: [u8; 32], // Hash of the off-chain game result backing the outcome
+    pub closed: bool, // Escrow closed; the round account stays so its id can't be reused
+    pub sudden_death_used: bool,
+    pub hp_update_count: u32,
+    pub funding_end_time: i64, // Crowdfunded rounds take contributions until this time, then betting opens
+    pub fight_duration: i64,   // fight_end_time is re-based on this when the fight actually starts
+    pub fight_start_time: i64, // 0 until start_fight_phase runs
+    pub winning_predictions: u8, // Bitmask of BossPrediction::mask() values that pay out
+    pub payout_pool: u64, // Amount winners split, fixed at end_fight: min(prize pool, payout budget)
+    pub operator: Pubkey, // Runs the fight (HP updates, outcome); money and admin stay with authority
+    pub fight_ended_at: i64, // When end_fight resolved the round; vesting starts here
+    pub counted_as_open: bool, // Holds a GlobalStats open-round slot until closed
+    pub outstanding_obligation: u64, // Still owed to paid winners who haven't fully claimed
+    pub total_bonus_paid: u64,
+    pub total_refunded: u64,
+    pub sweep_cursor: u64, // One past the last bet_index force_claim_remaining visited; the next sweep starts here
+    pub underfunded: bool, // Escrow held less than the winners' obligation at resolution
+    pub claims_open: bool, // claim_payout is accepted; set at end_fight or later by open_claims
+    pub winning_prediction: BossPrediction, // The outcome that happened; meaningful once Ended
+    pub escrow: Pubkey,    // The round's escrow token account, so clients need not derive it
+    pub mock_time: i64,    // test-time builds only: overrides the clock for this round when nonzero
+    pub time_buckets: [u32; BET_TIME_BUCKETS], // Bets per equal slice of the betting window
+    #[max_len(64)]
+    pub operator_note: String, // Live commentary shown by clients during the fight
+    pub seed_reclaimed: bool, // reclaim_excess_seed already ran
+    pub blocklist_enabled: bool, // Bets must pass the round's Blocklist account
+    pub merkle_bets_committed: bool, // Off-chain bet tree committed via commit_merkle_bets
+    pub losing_bets_closed: u64, // Bets closed by close_losing_bet
+    pub total_contributed: u64, // Crowdfunded into the pool via contribute_to_pool
+    pub contributions_refunded: u64, // Returned to contributors of a cancelled round
+    pub claim_bitmap_enabled: bool, // Every bet must be recorded in the round's ClaimBitmap
 }
 
 #[account]
@@ -40,580 +97,5728 @@ pub struct BetAccount {
     pub username: String,
     pub timestamp: i64,
     pub payout_claimed: bool,
+    pub bonus_claimed: bool,
+    pub side_rank: u64, // Earlier bets on the same prediction; placement order breaks timestamp ties
+    pub rent_payer: Pubkey, // Receives the rent back when the bet account closes
+    pub betting_end_time_snapshot: i64, // Round's betting_end_time when this bet was accepted
+    pub bet_index: u64, // Stable placement order within the round; indexes the claim bitmap
+    pub claimed_so_far: u64, // Paid out of this bet's share; payout_claimed once it reaches the full share
+    pub is_late: bool,       // Placed during the late-bet grace window; pays out at a penalty
+    #[max_len(64)]
+    pub memo: String, // Free-form client tag, e.g. which UI placed the bet
+    pub round: Pubkey, // The BettingRound this bet was placed in; round_id is only unique per authority
+    pub original_bettor: Pubkey, // Who placed the bet; never changes, so it keys the bet's PDA and merkle leaf after a transfer
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
-pub enum GamePhase {
-    Betting,
-    Fighting,
-    Ended,
-}
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
-pub enum BossPrediction {
-    Death,
-    Survival,
-}
-
-// =================================================================
-// ✅ EVENTS ✅
-// =================================================================
-
-#[event]
-pub struct BettingRoundInitialized {
-    pub round_id: u64,
-    pub betting_end_time: i64,
-    pub fight_end_time: i64,
-    pub token_mint: Pubkey,
-    pub prize_pool_amount: u64,  // NEW
-}
-
-#[event]
-pub struct BetPlaced {
-    pub round_id: u64,
-    pub bettor: Pubkey,
-    pub prediction: BossPrediction,
-    pub username: String,
-}
-
-#[event]
-pub struct FightPhaseStarted {
-    pub round_id: u64,
-    pub fight_end_time: i64,
-}
-
-#[event]
-pub struct BossHpUpdated {
-    pub round_id: u64,
-    pub new_hp: u32,
-}
-
-#[event]
-pub struct FightEnded {
-    pub round_id: u64,
-    pub boss_defeated: bool,
-}
-
-#[event]
-pub struct PayoutClaimed {
-    pub round_id: u64,
-    pub bettor: Pubkey,
-    pub payout_amount: u64,  // CHANGED: Just the equal share amount
-}
-
-// =================================================================
-// ⭐️ PROGRAM INSTRUCTIONS ⭐️
-// =================================================================
-#[program]
-pub mod boss_fight_betting {
-    use super::*;
-
-    /// Initialize a new betting round with treasury-funded prize pool
-    pub fn initialize_betting_round(
-        ctx: Context<InitializeBettingRound>,
-        round_id: u64,
-        betting_duration: i64,
-        fight_duration: i64,
-        initial_hp: u32,
-        prize_pool_amount: u64,  // NEW: Treasury funds this amount
-    ) -> Result<()> {
-        let betting_round = &mut ctx.accounts.betting_round;
-        let clock = Clock::get()?;
-
-        betting_round.round_id = round_id;
-        betting_round.authority = ctx.accounts.authority.key();
-        betting_round.treasury = ctx.accounts.treasury.key();
-        betting_round.token_mint = ctx.accounts.token_mint.key();
-        betting_round.betting_start_time = clock.unix_timestamp;
-        
-        betting_round.betting_end_time = clock.unix_timestamp
-            .checked_add(betting_duration)
-            .ok_or(BettingError::ArithmeticOverflow)?;
-        
-        betting_round.fight_end_time = clock.unix_timestamp
-            .checked_add(betting_duration)
-            .ok_or(BettingError::ArithmeticOverflow)?
-            .checked_add(fight_duration)
-            .ok_or(BettingError::ArithmeticOverflow)?;
-        
-        betting_round.initial_hp = initial_hp;
-        betting_round.current_hp = initial_hp;
-        betting_round.prize_pool_amount = prize_pool_amount;
-        betting_round.phase = GamePhase::Betting;
-        betting_round.total_death_bets = 0;
-        betting_round.total_survival_bets = 0;
-        betting_round.total_bets_count = 0;
-        betting_round.boss_defeated = false;
-        betting_round.payouts_processed = false;
-        betting_round.escrow_bump = ctx.bumps.escrow_token_account;
-
-        // Transfer prize pool from treasury to escrow
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.treasury_token_account.to_account_info(),
-                    to: ctx.accounts.escrow_token_account.to_account_info(),
-                    authority: ctx.accounts.treasury.to_account_info(),
-                },
-            ),
-            prize_pool_amount,
-        )?;
-
-        emit!(BettingRoundInitialized {
-            round_id,
-            betting_end_time: betting_round.betting_end_time,
-            fight_end_time: betting_round.fight_end_time,
-            token_mint: betting_round.token_mint,
-            prize_pool_amount,
-        });
-
-        Ok(())
-    }
-
-    /// Place a bet on boss death or survival (NO TOKENS REQUIRED)
-    pub fn place_bet(
-        ctx: Context<PlaceBet>,
+impl BettingRound {
+    /// Validate a new bet against the round and record it (NO TOKEN TRANSFER).
+    /// `now` must come from `round_clock`, never from instruction data.
+    pub fn accept_bet(
+        &mut self,
+        bet_account: &mut BetAccount,
+        bettor: Pubkey,
+        rent_payer: Pubkey,
         prediction: BossPrediction,
         username: String,
+        now: i64,
     ) -> Result<()> {
-        let betting_round = &mut ctx.accounts.betting_round;
-        let bet_account = &mut ctx.accounts.bet_account;
-        let clock = Clock::get()?;
-
-        // Explicit signer check
+        // Validate betting phase and timing
         require!(
-            ctx.accounts.bettor.is_signer,
-            BettingError::Unauthorized
+            self.phase != GamePhase::Funding,
+            BettingError::RoundInFunding
         );
-
-        // Validate betting phase and timing
+        require!(!self.config.merkle_bets, BettingError::BetsAreOffChain);
         require!(
-            betting_round.phase == GamePhase::Betting,
+            self.phase == GamePhase::Betting,
             BettingError::NotInBettingPhase
         );
+        require!(now >= self.funding_end_time, BettingError::FundingStillOpen);
+        require!(
+            now >= self.betting_start_time,
+            BettingError::BettingNotStarted
+        );
         require!(
-            clock.unix_timestamp <= betting_round.betting_end_time,
+            now <= self.betting_close_time()?,
             BettingError::BettingPeriodExpired
         );
+        require!(!self.bets_frozen, BettingError::BetsFrozen);
         require!(username.len() <= 32, BettingError::UsernameTooLong);
 
-        // Initialize bet account (NO TOKEN TRANSFER)
-        bet_account.bettor = ctx.accounts.bettor.key();
-        bet_account.round_id = betting_round.round_id;
+        // Initialize bet account
+        bet_account.bettor = bettor;
+        bet_account.original_bettor = bettor;
+        bet_account.round_id = self.round_id;
         bet_account.prediction = prediction.clone();
         bet_account.username = username;
-        bet_account.timestamp = clock.unix_timestamp;
+        bet_account.timestamp = now;
         bet_account.payout_claimed = false;
+        bet_account.bonus_claimed = false;
+        bet_account.rent_payer = rent_payer;
+        bet_account.betting_end_time_snapshot = self.betting_end_time;
+        bet_account.bet_index = self.total_bets_count;
+        bet_account.claimed_so_far = 0;
+        bet_account.is_late = now > self.betting_end_time;
+        bet_account.side_rank = match prediction {
+            BossPrediction::Death => self.total_death_bets,
+            BossPrediction::Survival => self.total_survival_bets,
+        };
 
         // Update betting round counts
-        match prediction {
-            BossPrediction::Death => betting_round.total_death_bets += 1,
-            BossPrediction::Survival => betting_round.total_survival_bets += 1,
+        let side_total = match prediction {
+            BossPrediction::Death => &mut self.total_death_bets,
+            BossPrediction::Survival => &mut self.total_survival_bets,
+        };
+        *side_total = side_total
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        self.total_bets_count = self
+            .total_bets_count
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        let bucket = self.time_bucket(now);
+        self.time_buckets[bucket] = self.time_buckets[bucket]
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        // Bets are only accepted while Betting, so HP stops scaling once the fight starts
+        if self.config.hp_per_bet > 0 {
+            self.initial_hp = self
+                .initial_hp
+                .checked_add(self.config.hp_per_bet)
+                .ok_or(BettingError::ArithmeticOverflow)?;
+            self.current_hp = self
+                .current_hp
+                .checked_add(self.config.hp_per_bet)
+                .ok_or(BettingError::ArithmeticOverflow)?;
         }
-        betting_round.total_bets_count += 1;
 
         emit!(BetPlaced {
-            round_id: betting_round.round_id,
-            bettor: ctx.accounts.bettor.key(),
-            prediction: prediction.clone(),
-            username: bet_account.username.clone(),
+            round_id: self.round_id,
+            bettor,
+            prediction_code: prediction.code(),
+            prediction,
+            username: if self.config.anonymous_bets {
+                String::new()
+            } else {
+                bet_account.username.clone()
+            },
+            is_late: bet_account.is_late,
         });
 
         Ok(())
     }
 
-    /// Start the fighting phase
-    pub fn start_fight_phase(ctx: Context<StartFightPhase>) -> Result<()> {
-        let betting_round = &mut ctx.accounts.betting_round;
-        let clock = Clock::get()?;
-
-        require!(
-            betting_round.phase == GamePhase::Betting,
-            BettingError::NotInBettingPhase
-        );
-        require!(
-            clock.unix_timestamp >= betting_round.betting_end_time,
-            BettingError::BettingStillActive
-        );
-        require!(
-            ctx.accounts.authority.key() == betting_round.authority,
-            BettingError::Unauthorized
-        );
+    /// Fill in a freshly created round; shared by every round-creation instruction
+    pub fn open(
+        &mut self,
+        params: RoundParams,
+        authority: Pubkey,
+        treasury: Pubkey,
+        token_mint: &InterfaceAccount<Mint>,
+        escrow_bump: u8,
+        now: i64,
+    ) -> Result<()> {
+        params.validate()?;
+        let schedule = params.schedule(now)?;
+        let config = params.config;
 
-        betting_round.phase = GamePhase::Fighting;
+        self.round_id = params.round_id;
+        self.authority = authority;
+        self.operator = authority;
+        self.treasury = treasury;
+        self.token_mint = token_mint.key();
+        self.funding_end_time = schedule.funding_end_time;
+        self.betting_start_time = schedule.betting_start_time;
+        self.betting_end_time = schedule.betting_end_time;
+        self.fight_end_time = schedule.fight_end_time;
 
-        emit!(FightPhaseStarted {
-            round_id: betting_round.round_id,
-            fight_end_time: betting_round.fight_end_time,
-        });
+        self.fight_duration = params.fight_duration;
+        self.initial_hp = params.initial_hp;
+        self.current_hp = params.initial_hp;
+        self.prize_pool_amount = params.prize_pool_amount;
+        self.token_decimals = token_mint.decimals;
+        // Only crowdfunded rounds wait on `open_betting`; scheduled treasury rounds open
+        // themselves at betting_start_time
+        self.phase = if config.prize_pool_source == PrizePoolSource::Crowdfunded {
+            GamePhase::Funding
+        } else {
+            GamePhase::Betting
+        };
+        self.total_death_bets = 0;
+        self.total_survival_bets = 0;
+        self.total_bets_count = 0;
+        self.boss_defeated = false;
+        self.payouts_processed = false;
+        self.escrow_bump = escrow_bump;
+        self.config = config;
+        self.claim_deadline = 0;
+        self.total_claimed_count = 0;
+        self.total_paid_out = 0;
+        self.unclaimed_redistributed = false;
+        self.bonus_per_winner = 0;
+        self.bets_merkle_root = [0u8; 32];
+        self.bets_frozen = false;
+        self.outcome_attestation = [0u8; 32];
+        self.closed = false;
+        self.sudden_death_used = false;
+        self.hp_update_count = 0;
+        self.fight_start_time = 0;
+        self.winning_predictions = 0;
+        self.payout_pool = 0;
+        self.fight_ended_at = 0;
+        self.counted_as_open = false;
+        self.outstanding_obligation = 0;
+        self.total_bonus_paid = 0;
+        self.total_refunded = 0;
+        self.sweep_cursor = 0;
+        self.underfunded = false;
+        self.mock_time = 0;
+        self.time_buckets = [0; BET_TIME_BUCKETS];
+        self.operator_note = String::new();
+        self.seed_reclaimed = false;
+        self.blocklist_enabled = false;
+        self.merkle_bets_committed = false;
+        self.losing_bets_closed = 0;
+        self.total_contributed = 0;
+        self.contributions_refunded = 0;
+        self.claim_bitmap_enabled = false;
+        self.claims_open = false;
+        self.winning_prediction = BossPrediction::Death;
 
         Ok(())
     }
 
-    /// Update boss HP
-    pub fn update_boss_hp(ctx: Context<UpdateBossHp>, new_hp: u32) -> Result<()> {
-        let betting_round = &mut ctx.accounts.betting_round;
-        let clock = Clock::get()?;
+    /// Whether the post-resolution claim cooldown has elapsed
+    pub fn claims_cooled_down(&self, now: i64) -> Result<bool> {
+        let cooldown_ends_at = self
+            .fight_ended_at
+            .checked_add(self.config.claim_cooldown)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        Ok(now >= cooldown_ends_at)
+    }
+
+    /// Whether the post-resolution settlement delay has elapsed
+    pub fn settled(&self, now: i64) -> Result<bool> {
+        let settles_at = self
+            .fight_ended_at
+            .checked_add(self.config.settlement_delay)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        Ok(now >= settles_at)
+    }
+
+    /// Whether every round-level claim gate passes at `now`: ended, claims opened, past the
+    /// cooldown and settlement delay, and inside the claim window
+    pub fn claimable_now(&self, now: i64) -> Result<bool> {
+        Ok(self.phase == GamePhase::Ended
+            && self.claims_open
+            && self.claims_cooled_down(now)?
+            && self.settled(now)?
+            && (self.claim_deadline == 0 || now <= self.claim_deadline))
+    }
+
+    /// Whether `prediction` is in the resolved winning set
+    pub fn is_winning(&self, prediction: &BossPrediction) -> bool {
+        self.winning_predictions & prediction.mask() != 0
+    }
+
+    /// Number of bets on any winning side
+    pub fn total_winners(&self) -> u64 {
+        let mut total = 0;
+        if self.is_winning(&BossPrediction::Death) {
+            total += self.total_death_bets;
+        }
+        if self.is_winning(&BossPrediction::Survival) {
+            total += self.total_survival_bets;
+        }
+        total
+    }
+
+    /// Share of bets on each side in basis points, always summing to 10_000
+    pub fn odds(&self) -> Odds {
+        if self.total_bets_count == 0 {
+            return Odds {
+                death_bps: 5_000,
+                survival_bps: 5_000,
+            };
+        }
 
+        let death_bps =
+            (self.total_death_bets as u128 * 10_000 / self.total_bets_count as u128) as u16;
+        Odds {
+            death_bps,
+            survival_bps: 10_000 - death_bps,
+        }
+    }
+
+    /// Record a live HP reading during the fight
+    pub fn apply_hp(&mut self, new_hp: u32, now: i64) -> Result<()> {
         require!(
-            betting_round.phase == GamePhase::Fighting,
+            self.phase == GamePhase::Fighting,
             BettingError::NotInFightPhase
         );
-        require!(
-            clock.unix_timestamp <= betting_round.fight_end_time,
-            BettingError::FightPeriodExpired
-        );
-        require!(
-            ctx.accounts.authority.key() == betting_round.authority,
-            BettingError::Unauthorized
-        );
+        // Inclusive: the fight_end_time second itself still takes HP (see `resolve`)
+        require!(now <= self.fight_end_time, BettingError::FightPeriodExpired);
+        require!(new_hp <= self.initial_hp, BettingError::HpExceedsInitial);
+
+        // Nothing changed: skip the write and the event so indexers don't see noise
+        if new_hp == self.current_hp {
+            return Ok(());
+        }
 
-        betting_round.current_hp = new_hp;
+        self.current_hp = new_hp;
+        self.hp_update_count = self
+            .hp_update_count
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
 
         emit!(BossHpUpdated {
-            round_id: betting_round.round_id,
+            round_id: self.round_id,
             new_hp,
         });
 
         Ok(())
     }
 
-    /// End the fight and determine outcome
-    pub fn end_fight(ctx: Context<EndFight>, final_hp: u64) -> Result<()> {
-        let betting_round = &mut ctx.accounts.betting_round;
-        let clock = Clock::get()?;
+    /// Absolute HP for a reading of `hp_bps` basis points of the initial HP
+    pub fn hp_from_bps(&self, hp_bps: u16) -> Result<u32> {
+        require!(hp_bps <= 10_000, BettingError::InvalidHp);
 
+        Ok((self.initial_hp as u64 * hp_bps as u64 / 10_000) as u32)
+    }
+
+    /// Whether the boss is alive but below the sudden-death threshold
+    pub fn sudden_death_eligible(&self) -> bool {
+        self.current_hp > 0 && self.current_hp < self.config.sudden_death_threshold
+    }
+
+    /// Resolve a finished fight to Ended (or Cancelled when voided for no activity)
+    pub fn resolve(
+        &mut self,
+        final_hp: u64,
+        outcome_attestation: [u8; 32],
+        winning_predictions: u8,
+        now: i64,
+    ) -> Result<()> {
         require!(
-            betting_round.phase == GamePhase::Fighting,
+            self.phase == GamePhase::Fighting,
             BettingError::NotInFightPhase
         );
-        require!(
-            ctx.accounts.authority.key() == betting_round.authority,
-            BettingError::Unauthorized
-        );
 
-        let fight_expired = clock.unix_timestamp >= betting_round.fight_end_time;
-        
-        betting_round.current_hp = final_hp as u32;
+        // Also inclusive, so at exactly fight_end_time both an HP update and the end are
+        // valid. The overlap is benign: resolving takes final_hp explicitly and closes the
+        // fight, after which no update lands.
+        let fight_expired = now >= self.fight_end_time;
+
+        self.current_hp = u32::try_from(final_hp).map_err(|_| BettingError::ArithmeticOverflow)?;
         let boss_dead = final_hp == 0;
 
         require!(fight_expired || boss_dead, BettingError::FightNotFinished);
+        let winning_predictions = BossPrediction::outcome_mask(boss_dead, winning_predictions)?;
+        self.check_winning_sides(winning_predictions)?;
 
-        betting_round.phase = GamePhase::Ended;
-        betting_round.boss_defeated = boss_dead;
+        // The operator never reported any HP: void the round rather than award survival
+        if fight_expired && self.config.void_on_no_activity && self.hp_update_count == 0 {
+            self.phase = GamePhase::Cancelled;
+            self.outcome_attestation = outcome_attestation;
+
+            emit!(RoundCancelled {
+                round_id: self.round_id,
+            });
+
+            return Ok(());
+        }
+
+        self.phase = GamePhase::Ended;
+        self.boss_defeated = boss_dead;
+        self.winning_predictions = winning_predictions;
+        self.outcome_attestation = outcome_attestation;
+        self.fight_ended_at = now;
+        self.claims_open = self.config.auto_open_claims;
+        self.winning_prediction = BossPrediction::outcome(boss_dead);
+        self.payout_pool = if self.config.payout_budget > 0 {
+            self.prize_pool_amount.min(self.config.payout_budget)
+        } else {
+            self.prize_pool_amount
+        };
+        self.outstanding_obligation = self.total_obligation()?;
+        if self.config.claim_window > 0 {
+            self.claim_deadline = now
+                .checked_add(self.config.claim_window)
+                .ok_or(BettingError::ArithmeticOverflow)?;
+        }
 
         emit!(FightEnded {
-            round_id: betting_round.round_id,
+            round_id: self.round_id,
+            game_name: self.config.game_name.clone(),
             boss_defeated: boss_dead,
+            winning_prediction: self.winning_prediction.clone(),
+            winning_predictions,
+            outcome_attestation,
         });
 
         Ok(())
     }
 
-    /// Claim equal share of prize pool for winning bet
-    pub fn claim_payout(ctx: Context<ClaimPayout>) -> Result<()> {
-        let betting_round = &ctx.accounts.betting_round;
-        let bet_account = &mut ctx.accounts.bet_account;
+    /// Flag a resolved round whose escrow can't cover its winners' obligation
+    pub fn check_funded(&mut self, escrow_balance: u64) {
+        if self.phase == GamePhase::Ended && escrow_balance < self.outstanding_obligation {
+            self.underfunded = true;
+            msg!(
+                "round {} underfunded: escrow {} < obligation {}",
+                self.round_id,
+                escrow_balance,
+                self.outstanding_obligation
+            );
+        }
+    }
 
+    /// `place_bet` takes its bet account by `init_if_needed`, so it must reject both a bet that
+    /// already exists and a closed one being re-created. A fresh account is all zeroes while
+    /// a placed bet always records its bettor; bets are only closed once a round has ended or
+    /// been cancelled, and those phases never take bets.
+    pub fn check_bet_slot(&self, bet_account: &BetAccount) -> Result<()> {
         require!(
-            betting_round.phase == GamePhase::Ended,
-            BettingError::FightNotEnded
+            matches!(self.phase, GamePhase::Funding | GamePhase::Betting),
+            BettingError::NotInBettingPhase
         );
         require!(
-            !bet_account.payout_claimed,
-            BettingError::PayoutAlreadyClaimed
+            bet_account.bettor == Pubkey::default(),
+            BettingError::AlreadyBet
+        );
+
+        Ok(())
+    }
+
+    /// Whether an ended round's outcome can no longer be amended at `now`: a winner has been
+    /// paid, the amendment window has run out, or the claim window has closed
+    pub fn outcome_final(&self, now: i64) -> Result<bool> {
+        let amend_until = self
+            .fight_ended_at
+            .checked_add(AMEND_WINDOW)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        Ok(self.total_paid_out > 0
+            || now > amend_until
+            || (self.claim_deadline > 0 && now > self.claim_deadline))
+    }
+
+    /// Contributions a cancelled round still owes back through `refund_contribution`
+    pub fn contributions_owed(&self) -> Result<u64> {
+        Ok(self
+            .total_contributed
+            .checked_sub(self.contributions_refunded)
+            .ok_or(BettingError::ArithmeticOverflow)?)
+    }
+
+    /// Leave the funding phase at `now`. Opening late pushes the betting and fight windows
+    /// back by the delay, so bettors still get the full scheduled betting window.
+    pub fn open_betting_at(&mut self, now: i64) -> Result<()> {
+        require!(
+            self.phase == GamePhase::Funding,
+            BettingError::NotInFundingPhase
         );
         require!(
-            bet_account.bettor == ctx.accounts.bettor.key(),
-            BettingError::Unauthorized
+            now >= self.betting_start_time,
+            BettingError::FundingStillOpen
         );
 
-        // Check if bet won
-        let won = match bet_account.prediction {
-            BossPrediction::Death => betting_round.boss_defeated,
-            BossPrediction::Survival => !betting_round.boss_defeated,
-        };
+        let delay = now - self.betting_start_time;
+        self.betting_start_time = now;
+        self.betting_end_time = self
+            .betting_end_time
+            .checked_add(delay)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        self.fight_end_time = self
+            .fight_end_time
+            .checked_add(delay)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        self.phase = GamePhase::Betting;
 
-        require!(won, BettingError::BetLost);
+        Ok(())
+    }
 
-        // Calculate equal share
-        let total_winners = if betting_round.boss_defeated {
-            betting_round.total_death_bets
+    /// Escrow still held for someone other than the treasury: a cancelled round's unrefunded
+    /// contributions and redistributed bonuses not yet claimed
+    pub fn escrow_owed(&self) -> Result<u64> {
+        let contributions = if self.phase == GamePhase::Cancelled {
+            self.contributions_owed()?
+        } else {
+            0
+        };
+        let bonuses = if self.unclaimed_redistributed {
+            self.bonus_per_winner
+                .checked_mul(self.total_claimed_count)
+                .and_then(|v| v.checked_sub(self.total_bonus_paid))
+                .ok_or(BettingError::ArithmeticOverflow)?
         } else {
-            betting_round.total_survival_bets
+            0
         };
 
-        require!(total_winners > 0, BettingError::NoWinners);
+        Ok(contributions
+            .checked_add(bonuses)
+            .ok_or(BettingError::ArithmeticOverflow)?)
+    }
 
-        // Equal split of prize pool
-        let payout_amount = (betting_round.prize_pool_amount as u128)
-            .checked_div(total_winners as u128)
-            .ok_or(BettingError::ArithmeticOverflow)?;
-        
-        let payout_u64 = u64::try_from(payout_amount)
-            .map_err(|_| BettingError::ArithmeticOverflow)?;
-        
+    /// Whether a resolved round's escrow holding `escrow_balance` may be closed at `now`:
+    /// nothing may still be owed out of it, and whatever it holds beyond that must be within
+    /// `dust_tolerance`, swept only once nothing is owed to winners or their claim window
+    /// has closed
+    pub fn check_closable(&self, escrow_balance: u64, now: i64) -> Result<()> {
+        let owed = self.escrow_owed()?;
+        let dust = escrow_balance.saturating_sub(owed);
         require!(
-            ctx.accounts.escrow_token_account.amount >= payout_u64,
-            BettingError::InsufficientEscrowFunds
+            dust <= self.config.dust_tolerance,
+            BettingError::EscrowNotEmpty
         );
+        require!(owed == 0, BettingError::FundsStillOwed);
+        if dust > 0 {
+            require!(
+                self.outstanding_obligation == 0
+                    || (self.claim_deadline > 0 && now > self.claim_deadline),
+                BettingError::EscrowNotEmpty
+            );
+        }
 
-        // Transfer equal share to winner
-        let round_id_bytes = betting_round.round_id.to_le_bytes();
-        let escrow_seeds: &[&[u8]] = &[
-            b"escrow",
-            round_id_bytes.as_ref(),
-            &[betting_round.escrow_bump],
-        ];
-        let signer_seeds = &[&escrow_seeds[..]];
-
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.escrow_token_account.to_account_info(),
-                    to: ctx.accounts.bettor_token_account.to_account_info(),
-                    authority: ctx.accounts.escrow_token_account.to_account_info(),
-                },
-                signer_seeds
-            ),
-            payout_u64,
-        )?;
+        Ok(())
+    }
 
-        bet_account.payout_claimed = true;
+    /// Tiered and top-K rounds rank winners within one side, so only one side may win;
+    /// otherwise each side's first K would all be paid out of a pool split K ways
+    pub fn check_winning_sides(&self, winning_predictions: u8) -> Result<()> {
+        let ranked = self.config.round_kind == RoundKind::Tiered || self.config.top_k_winners > 0;
+        require!(
+            !ranked || winning_predictions.count_ones() == 1,
+            BettingError::InvalidWinningPredictions
+        );
 
-        emit!(PayoutClaimed {
-            round_id: betting_round.round_id,
-            bettor: ctx.accounts.bettor.key(),
-            payout_amount: payout_u64,
-        });
-        
         Ok(())
     }
-}
 
-// =================================================================
-// ⭐️ ACCOUNTS CONTEXTS ⭐️
-// =================================================================
+    /// Everything owed to paid winners at resolution: each one's full share
+    pub fn total_obligation(&self) -> Result<u64> {
+        if self.paid_winners() == 0 {
+            return Ok(0);
+        }
 
-#[derive(Accounts)]
-#[instruction(round_id: u64)]
-pub struct InitializeBettingRound<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + BettingRound::INIT_SPACE,
-        seeds = [b"betting_round", round_id.to_le_bytes().as_ref()],
-        bump,
-        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != token_mint.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != treasury.key() @ BettingError::InvalidAccount
-    )]
-    pub betting_round: Account<'info, BettingRound>,
+        match self.config.round_kind {
+            RoundKind::EqualSplit => self
+                .winner_share()?
+                .checked_mul(self.paid_winners())
+                .ok_or(BettingError::ArithmeticOverflow.into()),
+            RoundKind::Tiered => (0..self.paid_winners()).try_fold(0u64, |total, rank| {
+                total
+                    .checked_add(self.tier_share(rank)?)
+                    .ok_or(BettingError::ArithmeticOverflow.into())
+            }),
+        }
+    }
 
-    #[account(
-        init,
-        payer = authority,
-        token::mint = token_mint,
-        token::authority = escrow_token_account,
-        seeds = [b"escrow", round_id.to_le_bytes().as_ref()],
-        bump,
-        constraint = escrow_token_account.key() != token_mint.key() @ BettingError::InvalidAccount,
-        constraint = escrow_token_account.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
-        constraint = escrow_token_account.key() != authority.key() @ BettingError::InvalidAccount,
-        constraint = escrow_token_account.key() != treasury.key() @ BettingError::InvalidAccount
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    /// Reduce the outstanding obligation after paying `amount` toward a bet; once the bet
+    /// is fully claimed, any late-bet penalty withheld from its share is released too
+    pub fn record_obligation_paid(
+        &mut self,
+        bet_account: &BetAccount,
+        amount: u64,
+        fully_claimed: bool,
+    ) -> Result<()> {
+        let mut released = amount;
+        if fully_claimed {
+            let penalty = self
+                .full_share(bet_account)?
+                .saturating_sub(self.bet_share(bet_account)?);
+            released = released
+                .checked_add(penalty)
+                .ok_or(BettingError::ArithmeticOverflow)?;
+        }
+        self.outstanding_obligation = self
+            .outstanding_obligation
+            .checked_sub(released)
+            .ok_or(BettingError::ArithmeticOverflow)?;
 
-    #[account(
-        constraint = token_mint.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
-        constraint = token_mint.key() != authority.key() @ BettingError::InvalidAccount,
-        constraint = token_mint.key() != treasury.key() @ BettingError::InvalidAccount,
-        constraint = token_mint.key() != system_program.key() @ BettingError::InvalidAccount,
-        constraint = token_mint.key() != token_program.key() @ BettingError::InvalidAccount,
-        constraint = token_mint.key() != rent.key() @ BettingError::InvalidAccount
-    )]
-    pub token_mint: Account<'info, Mint>,
+        Ok(())
+    }
 
-    // Treasury token account (must have funds to deposit prize pool)
-    #[account(
-        mut,
-        constraint = treasury_token_account.mint == token_mint.key() @ BettingError::InvalidTokenMint,
-        constraint = treasury_token_account.owner == treasury.key() @ BettingError::InvalidTokenAccount,
-        constraint = treasury_token_account.key() != authority.key() @ BettingError::InvalidAccount,
-        constraint = treasury_token_account.key() != treasury.key() @ BettingError::InvalidAccount,
-        constraint = treasury_token_account.key() != system_program.key() @ BettingError::InvalidAccount,
-        constraint = treasury_token_account.key() != token_program.key() @ BettingError::InvalidAccount
-    )]
-    pub treasury_token_account: Account<'info, TokenAccount>,
+    /// Move the sweep cursor past `bet_index`, refusing a bet the sweep has already passed
+    pub fn advance_sweep(&mut self, bet_index: u64) -> Result<()> {
+        require!(
+            bet_index >= self.sweep_cursor,
+            BettingError::SweepOutOfOrder
+        );
+        self.sweep_cursor = bet_index
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
 
-    #[account(
-        mut,
-        constraint = authority.key() != system_program.key() @ BettingError::InvalidAccount,
-        constraint = authority.key() != token_program.key() @ BettingError::InvalidAccount,
-        constraint = authority.key() != rent.key() @ BettingError::InvalidAccount
-    )]
-    pub authority: Signer<'info>,
+        Ok(())
+    }
 
-    /// CHECK: Treasury account (must sign to authorize prize pool deposit)
-    #[account(
-        mut,
-        constraint = treasury.key() != system_program.key() @ BettingError::InvalidAccount,
-        constraint = treasury.key() != token_program.key() @ BettingError::InvalidAccount,
-        constraint = treasury.key() != rent.key() @ BettingError::InvalidAccount
-    )]
-    pub treasury: Signer<'info>,
+    /// What the escrow should hold according to the round's own accounting
+    pub fn expected_escrow_balance(&self) -> Result<u64> {
+        Ok(self
+            .prize_pool_amount
+            .checked_sub(self.total_paid_out)
+            .and_then(|v| v.checked_sub(self.total_bonus_paid))
+            .and_then(|v| v.checked_sub(self.total_refunded))
+            .ok_or(BettingError::InvariantViolation)?)
+    }
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub rent: Sysvar<'info, Rent>,
+    /// Leftover escrow `redistribute_unclaimed` shares out; funds above the payout budget are
+    /// never shared out to winners
+    pub fn bonus_pool(&self, escrow_balance: u64) -> u64 {
+        escrow_balance.min(self.payout_pool.saturating_sub(self.total_paid_out))
+    }
+
+    /// Part of the treasury seed sized for bets that never came:
+    /// `seed * (expected - actual) / expected`
+    pub fn excess_seed(&self) -> Result<u64> {
+        let expected_bets = self.config.expected_bets;
+        let missing_bets = expected_bets.saturating_sub(self.total_bets_count);
+        let amount = (self.prize_pool_amount as u128)
+            .checked_mul(missing_bets as u128)
+            .ok_or(BettingError::ArithmeticOverflow)?
+            .checked_div(expected_bets as u128)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        Ok(u64::try_from(amount).map_err(|_| BettingError::ArithmeticOverflow)?)
+    }
+
+    /// Histogram slot for a bet placed at `now`; late bets land in the last one
+    pub fn time_bucket(&self, now: i64) -> usize {
+        let window = self
+            .betting_end_time
+            .saturating_sub(self.betting_start_time)
+            .max(1) as i128;
+        let elapsed = now.saturating_sub(self.betting_start_time).max(0) as i128;
+        let bucket = elapsed * BET_TIME_BUCKETS as i128 / window;
+
+        bucket.min(BET_TIME_BUCKETS as i128 - 1) as usize
+    }
+
+    /// Whether the prize pool is committed and may no longer grow or shrink
+    pub fn pool_locked(&self) -> bool {
+        self.config.lock_pool_after_first_bet && self.total_bets_count > 0
+    }
+
+    /// Last moment a bet is accepted, including the late-bet grace window
+    pub fn betting_close_time(&self) -> Result<i64> {
+        Ok(self
+            .betting_end_time
+            .checked_add(self.config.late_bet_grace)
+            .ok_or(BettingError::ArithmeticOverflow)?)
+    }
+
+    /// A winning bet's full share under the round's kind, before any late-bet penalty
+    pub fn full_share(&self, bet_account: &BetAccount) -> Result<u64> {
+        match self.config.round_kind {
+            RoundKind::EqualSplit => self.winner_share(),
+            RoundKind::Tiered => self.tier_share(bet_account.side_rank),
+        }
+    }
+
+    /// A winning bet's full share under the round's kind, less the late-bet penalty
+    pub fn bet_share(&self, bet_account: &BetAccount) -> Result<u64> {
+        let share = self.full_share(bet_account)?;
+        if !bet_account.is_late {
+            return Ok(share);
+        }
+
+        let kept_bps = 10_000 - self.config.late_bet_penalty_bps as u128;
+        Ok((share as u128 * kept_bps / 10_000) as u64)
+    }
+
+    /// Portion of `share` vested at `now`; the whole share when vesting is off
+    pub fn vested_amount(&self, share: u64, now: i64) -> Result<u64> {
+        let duration = self.config.vesting_duration;
+        if duration == 0 {
+            return Ok(share);
+        }
+
+        let elapsed = now.saturating_sub(self.fight_ended_at).clamp(0, duration);
+        let vested = (share as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(BettingError::ArithmeticOverflow)?
+            / duration as u128;
+
+        Ok(u64::try_from(vested).map_err(|_| BettingError::ArithmeticOverflow)?)
+    }
+
+    /// Whether a winning bet at `side_rank` falls inside the top-K cutoff (and, for tiered
+    /// rounds, inside the tier table)
+    pub fn is_paid_rank(&self, side_rank: u64) -> bool {
+        (self.config.top_k_winners == 0 || side_rank < self.config.top_k_winners)
+            && (self.config.round_kind != RoundKind::Tiered
+                || side_rank < self.config.distribution_tiers.len() as u64)
+    }
+
+    /// A tiered round's payout for the winner at `rank`; nothing past the last tier
+    pub fn tier_share(&self, rank: u64) -> Result<u64> {
+        let bps = usize::try_from(rank)
+            .ok()
+            .and_then(|rank| self.config.distribution_tiers.get(rank))
+            .copied()
+            .unwrap_or(0);
+        let share = (self.payout_pool as u128)
+            .checked_mul(bps as u128)
+            .ok_or(BettingError::ArithmeticOverflow)?
+            / 10_000;
+
+        Ok(u64::try_from(share).map_err(|_| BettingError::ArithmeticOverflow)?)
+    }
+
+    /// Equal split of the budget-capped prize pool among paid winners
+    pub fn winner_share(&self) -> Result<u64> {
+        let payout_amount = (self.payout_pool as u128)
+            .checked_div(self.paid_winners() as u128)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        Ok(u64::try_from(payout_amount).map_err(|_| BettingError::ArithmeticOverflow)?)
+    }
+
+    /// Number of winners sharing the prize pool after the top-K cutoff and tier table
+    pub fn paid_winners(&self) -> u64 {
+        let mut paid = self.total_winners();
+        if self.config.top_k_winners > 0 {
+            paid = paid.min(self.config.top_k_winners);
+        }
+        if self.config.round_kind == RoundKind::Tiered {
+            paid = paid.min(self.config.distribution_tiers.len() as u64);
+        }
+        paid
+    }
 }
 
-#[derive(Accounts)]
-pub struct PlaceBet<'info> {
-    #[account(
-        mut,
-        constraint = betting_round.key() != bet_account.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != bettor.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != system_program.key() @ BettingError::InvalidAccount
-    )]
-    pub betting_round: Account<'info, BettingRound>,
+impl BetAccount {
+    /// Whether this bet was placed in `round`. Round ids repeat across authorities, so only
+    /// the round's address identifies it.
+    pub fn belongs_to(&self, round: &Pubkey) -> bool {
+        self.round == *round
+    }
 
-    #[account(
-        init,
-        payer = bettor,
-        space = 8 + BetAccount::INIT_SPACE,
-        seeds = [
-            b"bet",
-            betting_round.round_id.to_le_bytes().as_ref(),
-            bettor.key().as_ref()
-        ],
-        bump,
-        constraint = bet_account.key() != bettor.key() @ BettingError::InvalidAccount,
-        constraint = bet_account.key() != system_program.key() @ BettingError::InvalidAccount
-    )]
-    pub bet_account: Account<'info, BetAccount>,
+    /// Merkle leaf committing to this bet:
+    /// sha256(0x00 || original_bettor || prediction || timestamp)
+    pub fn merkle_leaf(&self) -> [u8; 32] {
+        hashv(&[
+            &[0u8],
+            self.original_bettor.as_ref(),
+            &[self.prediction.clone() as u8],
+            &self.timestamp.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
 
-    #[account(
-        mut,
-        constraint = bettor.key() != system_program.key() @ BettingError::InvalidAccount
-    )]
-    pub bettor: Signer<'info>,
+/// Merkle leaf for an off-chain bet: sha256(0x00 || index || bettor || prediction)
+pub fn merkle_bet_leaf(index: u64, bettor: &Pubkey, prediction: &BossPrediction) -> [u8; 32] {
+    hashv(&[
+        &[0u8],
+        &index.to_le_bytes(),
+        bettor.as_ref(),
+        &[prediction.clone() as u8],
+    ])
+    .to_bytes()
+}
 
-    pub system_program: Program<'info, System>,
+/// Off-chain bets a round's `MerkleClaims` can track
+pub const MERKLE_CLAIMS_CAPACITY: u64 = 65_536;
+pub const MERKLE_CLAIMS_BYTES: usize = (MERKLE_CLAIMS_CAPACITY / 8) as usize;
+
+/// Claimed flags for a merkle round's leaves; bit i is set once leaf i has been paid.
+/// Zero-copy because the bitmap is too large to deserialize onto the stack.
+#[account(zero_copy)]
+pub struct MerkleClaims {
+    pub round_id: u64,
+    pub claimed: [u8; MERKLE_CLAIMS_BYTES],
+    pub bump: u8,
+    pub _padding: [u8; 7],
 }
 
-#[derive(Accounts)]
-pub struct StartFightPhase<'info> {
-    #[account(
-        mut,
-        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
-    )]
-    pub betting_round: Account<'info, BettingRound>,
+impl MerkleClaims {
+    pub fn is_claimed(&self, index: u64) -> bool {
+        self.claimed[(index / 8) as usize] & (1 << (index % 8)) != 0
+    }
 
-    pub authority: Signer<'info>,
+    pub fn mark_claimed(&mut self, index: u64) {
+        self.claimed[(index / 8) as usize] |= 1 << (index % 8);
+    }
 }
 
-#[derive(Accounts)]
-pub struct UpdateBossHp<'info> {
-    #[account(
-        mut,
-        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
-    )]
-    pub betting_round: Account<'info, BettingRound>,
+/// Maximum entries kept on a round's winners board
+pub const WINNERS_BOARD_CAPACITY: usize = 20;
 
-    pub authority: Signer<'info>,
+/// Bounded log of the most recent claims for a round, for cheap leaderboard reads.
+/// The full winner list is available via getProgramAccounts on `BetAccount` with a
+/// memcmp filter on `round_id` (offset 40: 8-byte discriminator + 32-byte bettor).
+#[account]
+#[derive(InitSpace)]
+pub struct WinnersBoard {
+    pub round_id: u64,
+    #[max_len(WINNERS_BOARD_CAPACITY)]
+    pub entries: Vec<WinnerEntry>,
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct EndFight<'info> {
-    #[account(
-        mut,
-        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
-    )]
-    pub betting_round: Account<'info, BettingRound>,
+/// Bets a round's claim bitmap can index
+pub const CLAIM_BITMAP_CAPACITY: u64 = 4096;
+/// Bytes per bitmap, one bit per bet
+pub const CLAIM_BITMAP_BYTES: usize = (CLAIM_BITMAP_CAPACITY / 8) as usize;
 
-    pub authority: Signer<'info>,
+/// Per-round bitmaps indexed by `BetAccount::bet_index`, so keepers can find unclaimed
+/// winners by reading one account. Bit i of `death_bets` is set if bet i predicted Death,
+/// bit i of `claimed` once its payout is claimed. It can only be created while the round has
+/// no bets. Once it exists every `place_bet` must pass it and `place_bets_batch` refuses the
+/// round; bets past `CLAIM_BITMAP_CAPACITY` are not tracked.
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimBitmap {
+    pub round_id: u64,
+    pub death_bets: [u8; CLAIM_BITMAP_BYTES],
+    pub claimed: [u8; CLAIM_BITMAP_BYTES],
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct ClaimPayout<'info> {
-    #[account(
-        constraint = betting_round.key() != bet_account.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != bettor.key() @ BettingError::InvalidAccount,
-        constraint = betting_round.key() != token_program.key() @ BettingError::InvalidAccount
-    )]
-    pub betting_round: Account<'info, BettingRound>,
+impl ClaimBitmap {
+    fn set_bit(bits: &mut [u8; CLAIM_BITMAP_BYTES], index: u64) {
+        if index < CLAIM_BITMAP_CAPACITY {
+            bits[(index / 8) as usize] |= 1 << (index % 8);
+        }
+    }
 
-    #[account(
-        mut, 
-        close = bettor, 
-        constraint = bet_account.round_id == betting_round.round_id,
-        constraint = bet_account.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
-        constraint = bet_account.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
-        constraint = bet_account.key() != bettor.key() @ BettingError::InvalidAccount,
-        constraint = bet_account.key() != token_program.key() @ BettingError::InvalidAccount
-    )]
-    pub bet_account: Account<'info, BetAccount>,
+    /// Record a newly placed bet's prediction
+    pub fn record_bet(&mut self, index: u64, prediction: &BossPrediction) {
+        if *prediction == BossPrediction::Death {
+            Self::set_bit(&mut self.death_bets, index);
+        }
+    }
 
-    #[account(
-        mut,
-        seeds = [b"escrow", betting_round.round_id.to_le_bytes().as_ref()],
-        bump,
-        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
-        constraint = escrow_token_account.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
-        constraint = escrow_token_account.key() != bettor.key() @ BettingError::InvalidAccount,
-        constraint = escrow_token_account.key() != token_program.key() @ BettingError::InvalidAccount
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    /// Mark a bet's payout as claimed
+    pub fn record_claim(&mut self, index: u64) {
+        Self::set_bit(&mut self.claimed, index);
+    }
+}
 
-    #[account(
-        mut,
-        constraint = bettor_token_account.owner == bettor.key() @ BettingError::InvalidTokenAccount,
-        constraint = bettor_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
-        constraint = bettor_token_account.key() != bettor.key() @ BettingError::InvalidAccount,
-        constraint = bettor_token_account.key() != token_program.key() @ BettingError::InvalidAccount
-    )]
-    pub bettor_token_account: Account<'info, TokenAccount>,
+/// Bettors a round's `Blocklist` can hold
+pub const BLOCKLIST_CAPACITY: usize = 32;
 
-    #[account(
-        mut,
-        constraint = bettor.key() != token_program.key() @ BettingError::InvalidAccount
-    )]
-    pub bettor: SystemAccount<'info>,
+/// Wallets barred from betting in a round, managed by the authority via `set_blocklist`
+#[account]
+#[derive(InitSpace)]
+pub struct Blocklist {
+    pub round_id: u64,
+    #[max_len(BLOCKLIST_CAPACITY)]
+    pub blocked: Vec<Pubkey>,
+    pub bump: u8,
+}
 
-    pub token_program: Program<'info, Token>,
+/// Running total one contributor has added to a crowdfunded round's pool
+#[account]
+#[derive(InitSpace)]
+pub struct Contribution {
+    pub round_id: u64,
+    pub contributor: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
 }
 
-// =================================================================
-// ⭐️ ERROR CODES ⭐️
-// =================================================================
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct WinnerEntry {
+    pub bettor: Pubkey,
+    #[max_len(32)]
+    pub username: String,
+    pub payout_amount: u64,
+}
 
-#[error_code]
-pub enum BettingError {
-    #[msg("Not in betting phase")]
-    NotInBettingPhase,
-    #[msg("Betting period has expired")]
-    BettingPeriodExpired,
-    #[msg("Username too long")]
-    UsernameTooLong,
-    #[msg("Not in fight phase")]
-    NotInFightPhase,
-    #[msg("Betting is still active")]
-    BettingStillActive,
-    #[msg("Fight period has expired")]
-    FightPeriodExpired,
-    #[msg("Fight has not finished")]
-    FightNotFinished,
-    #[msg("Fight has not ended")]
-    FightNotEnded,
-    #[msg("Payout already claimed")]
-    PayoutAlreadyClaimed,
+impl WinnersBoard {
+    /// Append a claim, dropping the oldest entry once the board is full
+    pub fn record(&mut self, entry: WinnerEntry) {
+        if self.entries.len() >= WINNERS_BOARD_CAPACITY {
+            self.entries.remove(0);
+        }
+        self.entries.push(entry);
+    }
+}
+
+/// Lifetime record for a player across every round, seeded by bettor
+#[account]
+#[derive(InitSpace)]
+pub struct UserProfile {
+    pub bettor: Pubkey,
+    pub bets_placed: u64,
+    pub bets_won: u64,
+    pub total_won: u64,
+    pub bump: u8,
+}
+
+/// Lifetime aggregates and round-creation limits for an operator, seeded by authority.
+/// Every round-creation instruction requires it, so call `initialize_global_stats` first.
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalStats {
+    pub authority: Pubkey,
+    pub rounds_created: u64,
+    pub total_pool_funded: u64,
+    pub total_paid_out: u64,
+    pub round_cooldown: i64, // Minimum seconds between rounds (0 = disabled)
+    pub last_round_created_at: i64,
+    pub max_concurrent_rounds: u64, // Cap on rounds created but not yet closed (0 = unlimited)
+    pub open_rounds: u64,
+    pub min_initial_hp: u32, // Lowest boss HP this authority's rounds may start with (0 = any non-zero)
+    pub bump: u8,
+}
+
+impl GlobalStats {
+    /// Authority-wide limits a new round must satisfy: HP floor, cooldown, open-round cap
+    pub fn check_round_creation(&self, now: i64, initial_hp: u32) -> Result<()> {
+        require!(
+            initial_hp >= self.min_initial_hp,
+            BettingError::InvalidInitialHp
+        );
+        let cooldown_ends_at = self
+            .last_round_created_at
+            .checked_add(self.round_cooldown)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        require!(now >= cooldown_ends_at, BettingError::CooldownActive);
+        require!(
+            self.max_concurrent_rounds == 0 || self.open_rounds < self.max_concurrent_rounds,
+            BettingError::TooManyOpenRounds
+        );
+
+        Ok(())
+    }
+
+    /// Enforce the creation cooldown and HP floor, and count a newly funded round
+    pub fn record_round_created(
+        &mut self,
+        now: i64,
+        prize_pool_amount: u64,
+        initial_hp: u32,
+    ) -> Result<()> {
+        self.check_round_creation(now, initial_hp)?;
+        self.count_round_created(now, prize_pool_amount)
+    }
+
+    /// Tally a round that passed `check_round_creation`
+    pub fn count_round_created(&mut self, now: i64, prize_pool_amount: u64) -> Result<()> {
+        self.last_round_created_at = now;
+        self.rounds_created = self
+            .rounds_created
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        self.total_pool_funded = self
+            .total_pool_funded
+            .checked_add(prize_pool_amount)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        self.open_rounds = self
+            .open_rounds
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    /// Free the open-round slot of a closed round
+    pub fn record_round_closed(&mut self) -> Result<()> {
+        self.open_rounds = self
+            .open_rounds
+            .checked_sub(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+}
+
+/// Operator-chosen rules for a round, fixed at creation. `Default` is the plainest round:
+/// an equal split of a treasury-funded pool with every option off.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq, InitSpace)]
+pub struct RoundConfig {
+    /// Seconds after `end_fight` during which winners may claim (0 = no deadline)
+    pub claim_window: i64,
+    /// Split leftover escrow among winners who claimed once the claim window closes
+    pub redistribute_unclaimed: bool,
+    /// Only the earliest K correct bettors share the prize (0 = every winner)
+    pub top_k_winners: u64,
+    /// Boss HP below which the fight may be extended once at timeout (0 = disabled)
+    pub sudden_death_threshold: u32,
+    /// Seconds a sudden-death extension adds from the moment it is called
+    pub sudden_death_duration: i64,
+    /// Payouts must go to the bettor's canonical associated token account
+    pub enforce_ata: bool,
+    /// Void the round instead of declaring survival if HP was never updated
+    pub void_on_no_activity: bool,
+    /// Where the prize pool comes from
+    pub prize_pool_source: PrizePoolSource,
+    /// Seconds before betting opens during which anyone may contribute (crowdfunded only)
+    pub funding_duration: i64,
+    /// Ceiling on the total paid to winners, snapshotted at `end_fight` (0 = whole pool)
+    pub payout_budget: u64,
+    /// Seconds after `end_fight` over which each winner's share vests linearly (0 = claim at once)
+    pub vesting_duration: i64,
+    /// Seconds after `betting_end_time` during which late bets are still accepted
+    pub late_bet_grace: i64,
+    /// Cut taken from a late bet's payout; it stays in escrow for `redistribute_unclaimed`
+    pub late_bet_penalty_bps: u16,
+    /// Leave usernames out of `BetPlaced` events; bet accounts still store them
+    pub anonymous_bets: bool,
+    /// Seconds after creation (and any funding period) before betting opens
+    pub betting_start_delay: i64,
+    /// Game mode; decides how winners' payouts are computed
+    pub round_kind: RoundKind,
+    /// Hash of the off-chain rules document; fixed at creation so clients can verify it
+    pub rules_hash: [u8; 32],
+    /// Seconds after `end_fight` before any claim is accepted, letting indexers catch up
+    pub claim_cooldown: i64,
+    /// Let bettors hand their bets, and the right to claim them, to another wallet
+    pub allow_bet_transfer: bool,
+    /// Open claims as soon as the fight ends; otherwise the authority calls `open_claims`
+    pub auto_open_claims: bool,
+    /// Make `place_bet` check the round's mint can still pay winners before taking a bet
+    pub validate_mint_on_bet: bool,
+    /// Boss HP added per accepted bet, so turnout makes a tougher fight (0 = fixed HP)
+    pub hp_per_bet: u32,
+    /// `Tiered` rounds only: bps of the payout pool for each winner rank, earliest first
+    #[max_len(MAX_DISTRIBUTION_TIERS)]
+    pub distribution_tiers: Vec<u16>,
+    /// Which game the round belongs to, echoed in its lifecycle events for multi-game clients
+    #[max_len(32)]
+    pub game_name: String,
+    /// Treasury rounds only: turnout the seed was sized for, letting `reclaim_excess_seed`
+    /// return the shortfall's share of it (0 = never reclaim)
+    pub expected_bets: u64,
+    /// Freeze the pool size once anyone has bet, so merges can't change what bettors play for
+    pub lock_pool_after_first_bet: bool,
+    /// Bets live off-chain in a merkle tree committed by the authority; winners claim with
+    /// `claim_payout_merkle` instead of holding a `BetAccount` each
+    pub merkle_bets: bool,
+    /// Treasury rounds only: pool the fight can't start below, reached by `add_installment`
+    /// after the initial funding (0 = the initial funding is the whole pool)
+    pub target_prize_pool: u64,
+    /// Rounding dust `close_betting_round` may sweep to the treasury instead of refusing to
+    /// close a non-empty escrow (0 = the escrow must be empty)
+    pub dust_tolerance: u64,
+    /// Seconds after `end_fight` before the first claim, so clients can index and show the
+    /// result; tuned separately from `claim_cooldown` (0 = no delay)
+    pub settlement_delay: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq, InitSpace)]
+#[repr(u8)]
+pub enum RoundKind {
+    #[default]
+    EqualSplit = 0,
+    Tiered = 1, // The k-th earliest winner gets distribution_tiers[k] bps of the pool
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq, InitSpace)]
+#[repr(u8)]
+pub enum PrizePoolSource {
+    #[default]
+    Treasury = 0,
+    Crowdfunded = 1,
+}
+
+// Borsh encodes these enums by variant position, so the explicit discriminants below are the
+// on-chain wire bytes and must match declaration order. Never reorder or insert variants:
+// new ones are appended with the next free value, as `GamePhase::Funding = 4` was after
+// `Cancelled`; a future Draw outcome would become `BossPrediction::Draw = 2`.
+
+/// Round lifecycle. Funding -> Betting (`open_betting`), Betting -> Fighting
+/// (`start_fight_phase`), Fighting -> Ended or Cancelled (`end_fight`); merges also cancel.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+#[repr(u8)]
+pub enum GamePhase {
+    Betting = 0,
+    Fighting = 1,
+    Ended = 2,
+    Cancelled = 3, // Voided: the pool returns to the treasury and bets are simply closed
+    Funding = 4,   // Crowdfunded rounds until open_betting; contributions in, no bets
+}
+
+/// Events also carry each prediction as a plain `prediction_code` (the discriminant below)
+/// for indexers that can't decode Anchor enums: 0 = Death, 1 = Survival.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+#[repr(u8)]
+pub enum BossPrediction {
+    Death = 0,
+    Survival = 1,
+}
+
+impl BossPrediction {
+    /// Every bit a winning-predictions mask may set
+    pub const ALL_MASK: u8 = 0b11;
+
+    /// Stable numeric code emitted as `prediction_code` in events
+    pub fn code(&self) -> u8 {
+        self.clone() as u8
+    }
+
+    /// This prediction's bit in a winning-predictions mask
+    pub fn mask(&self) -> u8 {
+        1 << self.code()
+    }
+
+    /// Validate an operator-supplied mask, or derive the single winner from the HP when it's 0
+    pub fn outcome_mask(boss_dead: bool, winning_predictions: u8) -> Result<u8> {
+        require!(
+            winning_predictions & !Self::ALL_MASK == 0,
+            BettingError::InvalidWinningPredictions
+        );
+
+        Ok(if winning_predictions != 0 {
+            winning_predictions
+        } else {
+            Self::outcome(boss_dead).mask()
+        })
+    }
+
+    /// The prediction that came true for a binary fight
+    pub fn outcome(boss_dead: bool) -> Self {
+        if boss_dead {
+            BossPrediction::Death
+        } else {
+            BossPrediction::Survival
+        }
+    }
+}
+
+/// Which instruction moved funds in an `EscrowBalanceChanged` event
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EscrowChangeReason {
+    Funded = 0,
+    Contribution = 1,
+    Payout = 2,
+    Bonus = 3,
+    Refund = 4,
+    Merge = 5,
+    DustSwept = 6,
+}
+
+/// Live side ratios returned by the `current_odds` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct Odds {
+    pub death_bps: u16,
+    pub survival_bps: u16,
+}
+
+/// A round's headline state returned by the `round_summary` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct RoundSummary {
+    pub phase: GamePhase,
+    pub current_hp: u32,
+    pub initial_hp: u32,
+    pub prize_pool_amount: u64,
+    pub total_bets_count: u64,
+    pub total_claimed_count: u64,
+    pub claim_deadline: i64,
+    pub claimable_now: bool,
+}
+
+/// A bet's standing returned by the `bet_result` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct BetResult {
+    pub won: bool,
+    pub claimable: u64,
+    pub already_claimed: bool,
+}
+
+/// Slices of the betting window in a round's `time_buckets` histogram
+pub const BET_TIME_BUCKETS: usize = 24;
+
+/// Shortest betting window a round may open with, in seconds
+pub const MIN_BETTING_DURATION: i64 = 60;
+
+/// Maximum bets accepted by a single `place_bets_batch` call
+pub const MAX_BATCH_BETS: usize = 5;
+
+/// Maximum paid ranks in a `Tiered` round
+pub const MAX_DISTRIBUTION_TIERS: usize = 10;
+
+/// Seconds after `end_fight` during which `amend_outcome` may still correct the result
+pub const AMEND_WINDOW: i64 = 24 * 60 * 60;
+
+/// Largest `dust_tolerance` a round may set, in base units; rounding dust is a few units
+/// per winner, never a meaningful share of the pool
+pub const MAX_DUST_TOLERANCE: u64 = 1_000;
+
+/// Maximum rounds created by a single `initialize_round_series` call
+pub const MAX_ROUND_SERIES: u8 = 4;
+
+/// Maximum winners paid by a single `resolve_and_distribute` call; larger rounds self-claim
+pub const MAX_DISTRIBUTE_WINNERS: usize = 8;
+
+/// One entry of a `place_bets_batch` call. Deliberately carries no timestamp: ranked and
+/// tiered payouts order bets by placement time, which only the clock may supply.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchBet {
+    pub prediction: BossPrediction,
+    pub username: String,
+    pub memo: String,
+}
+
+/// Per-round values supplied by the round-creation instructions
+#[derive(Default)]
+pub struct RoundParams {
+    pub round_id: u64,
+    pub betting_duration: i64,
+    pub fight_duration: i64,
+    pub initial_hp: u32,
+    pub prize_pool_amount: u64,
+    pub config: RoundConfig,
+}
+
+impl RoundParams {
+    /// Every creation-time check on the supplied parameters; mutates nothing
+    pub fn validate(&self) -> Result<()> {
+        let config = &self.config;
+
+        // A boss that starts dead makes the fight meaningless
+        require!(self.initial_hp > 0, BettingError::InvalidInitialHp);
+        require!(
+            self.betting_duration >= MIN_BETTING_DURATION,
+            BettingError::InvalidDuration
+        );
+        require!(config.claim_window >= 0, BettingError::InvalidClaimWindow);
+        require!(
+            !config.redistribute_unclaimed || config.claim_window > 0,
+            BettingError::InvalidClaimWindow
+        );
+        require!(
+            config.sudden_death_threshold == 0 || config.sudden_death_duration > 0,
+            BettingError::InvalidSuddenDeathConfig
+        );
+        require!(
+            match config.prize_pool_source {
+                PrizePoolSource::Treasury => config.funding_duration == 0,
+                PrizePoolSource::Crowdfunded => config.funding_duration > 0,
+            },
+            BettingError::InvalidFundingConfig
+        );
+        require!(
+            config.expected_bets == 0 || config.prize_pool_source == PrizePoolSource::Treasury,
+            BettingError::InvalidFundingConfig
+        );
+        // An installment plan needs a treasury to pay it and a pool that may still grow
+        require!(
+            config.target_prize_pool == 0
+                || (config.prize_pool_source == PrizePoolSource::Treasury
+                    && config.target_prize_pool >= self.prize_pool_amount
+                    && !config.lock_pool_after_first_bet),
+            BettingError::InvalidFundingConfig
+        );
+        // Off-chain bets carry no rank, lateness or claim record for later bonus rounds
+        require!(
+            !config.merkle_bets
+                || (config.round_kind == RoundKind::EqualSplit
+                    && config.top_k_winners == 0
+                    && config.vesting_duration == 0
+                    && !config.redistribute_unclaimed),
+            BettingError::InvalidMerkleConfig
+        );
+        require!(
+            config.late_bet_grace >= 0 && config.late_bet_penalty_bps <= 10_000,
+            BettingError::InvalidLateBetConfig
+        );
+        // Every winner must be able to vest fully before the claim window closes
+        require!(
+            config.vesting_duration >= 0
+                && (config.claim_window == 0 || config.vesting_duration <= config.claim_window),
+            BettingError::InvalidVestingConfig
+        );
+
+        require!(
+            config.betting_start_delay >= 0,
+            BettingError::InvalidStartDelay
+        );
+        require!(
+            config.claim_cooldown >= 0,
+            BettingError::InvalidClaimCooldown
+        );
+        // A delay outlasting the claim window would leave winners no time to claim
+        require!(
+            config.settlement_delay >= 0
+                && (config.claim_window == 0 || config.settlement_delay < config.claim_window),
+            BettingError::InvalidSettlementDelay
+        );
+        require!(
+            config.game_name.len() <= 32 && !config.game_name.chars().any(char::is_control),
+            BettingError::InvalidGameName
+        );
+        require!(
+            match config.round_kind {
+                RoundKind::EqualSplit => config.distribution_tiers.is_empty(),
+                RoundKind::Tiered => {
+                    !config.distribution_tiers.is_empty()
+                        && config.distribution_tiers.len() <= MAX_DISTRIBUTION_TIERS
+                        && config
+                            .distribution_tiers
+                            .iter()
+                            .map(|&bps| bps as u32)
+                            .sum::<u32>()
+                            <= 10_000
+                }
+            },
+            BettingError::InvalidDistributionTiers
+        );
+        require!(
+            config.dust_tolerance <= MAX_DUST_TOLERANCE,
+            BettingError::InvalidDustTolerance
+        );
+
+        Ok(())
+    }
+
+    /// Phase boundaries a round opened at `now` with these parameters would get
+    pub fn schedule(&self, now: i64) -> Result<RoundSchedule> {
+        // Crowdfunded rounds collect contributions first; betting opens when funding closes,
+        // plus any scheduled delay
+        let funding_end_time = now
+            .checked_add(self.config.funding_duration)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        let betting_start_time = funding_end_time
+            .checked_add(self.config.betting_start_delay)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        Ok(RoundSchedule {
+            funding_end_time,
+            betting_start_time,
+            betting_end_time: betting_start_time
+                .checked_add(self.betting_duration)
+                .ok_or(BettingError::ArithmeticOverflow)?,
+            fight_end_time: betting_start_time
+                .checked_add(self.betting_duration)
+                .ok_or(BettingError::ArithmeticOverflow)?
+                .checked_add(self.fight_duration)
+                .ok_or(BettingError::ArithmeticOverflow)?,
+        })
+    }
+}
+
+/// Phase boundaries returned by the `validate_round_params` view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct RoundSchedule {
+    pub funding_end_time: i64,
+    pub betting_start_time: i64,
+    pub betting_end_time: i64,
+    pub fight_end_time: i64,
+}
+
+// =================================================================
+// ✅ EVENTS ✅
+// =================================================================
+
+#[event]
+pub struct BettingRoundInitialized {
+    pub round_id: u64,
+    pub betting_end_time: i64,
+    pub fight_end_time: i64,
+    pub token_mint: Pubkey,
+    pub prize_pool_amount: u64, // NEW
+    pub decimals: u8,
+    pub round_kind: RoundKind,
+    pub rules_hash: [u8; 32],
+    pub game_name: String,
+}
+
+#[event]
+pub struct EscrowBalanceChanged {
+    pub round_id: u64,
+    pub new_balance: u64,
+    pub reason: EscrowChangeReason,
+}
+
+#[event]
+pub struct BetPlaced {
+    pub round_id: u64,
+    pub bettor: Pubkey,
+    pub prediction: BossPrediction,
+    pub prediction_code: u8,
+    pub username: String,
+    pub is_late: bool,
+}
+
+#[event]
+pub struct ContributionRefunded {
+    pub round_id: u64,
+    pub contributor: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PoolContribution {
+    pub round_id: u64,
+    pub contributor: Pubkey,
+    pub amount: u64,
+    pub prize_pool_amount: u64,
+}
+
+#[event]
+pub struct BetsFrozenChanged {
+    pub round_id: u64,
+    pub bets_frozen: bool,
+}
+
+#[event]
+pub struct ClaimsOpened {
+    pub round_id: u64,
+}
+
+#[event]
+pub struct OperatorChanged {
+    pub round_id: u64,
+    pub operator: Pubkey,
+}
+
+#[event]
+pub struct InstallmentAdded {
+    pub round_id: u64,
+    pub amount: u64,
+    pub prize_pool_amount: u64,
+    pub target_prize_pool: u64,
+}
+
+#[event]
+pub struct MerkleBetsCommitted {
+    pub round_id: u64,
+    pub bets_merkle_root: [u8; 32],
+    pub death_bets: u64,
+    pub survival_bets: u64,
+}
+
+#[event]
+pub struct BetTransferred {
+    pub round_id: u64,
+    pub bet_index: u64,
+    pub previous_bettor: Pubkey,
+    pub new_bettor: Pubkey,
+}
+
+#[event]
+pub struct BettingOpened {
+    pub round_id: u64,
+    pub prize_pool_amount: u64,
+    pub betting_end_time: i64,
+}
+
+#[event]
+pub struct FightPhaseStarted {
+    pub round_id: u64,
+    pub fight_start_time: i64,
+    pub fight_end_time: i64,
+    pub bets_merkle_root: [u8; 32],
+}
+
+#[event]
+pub struct BossHpUpdated {
+    pub round_id: u64,
+    pub new_hp: u32,
+}
+
+#[event]
+pub struct OperatorNote {
+    pub round_id: u64,
+    pub note: String,
+}
+
+#[event]
+pub struct SuddenDeath {
+    pub round_id: u64,
+    pub current_hp: u32,
+    pub fight_end_time: i64,
+}
+
+#[event]
+pub struct FightEnded {
+    pub round_id: u64,
+    pub game_name: String,
+    pub boss_defeated: bool,
+    pub winning_prediction: BossPrediction, // The outcome itself; winning_predictions is who gets paid
+    pub winning_predictions: u8,
+    pub outcome_attestation: [u8; 32],
+}
+
+#[event]
+pub struct OutcomeAmended {
+    pub round_id: u64,
+    pub old_boss_defeated: bool,
+    pub new_boss_defeated: bool,
+    pub old_winning_predictions: u8,
+    pub new_winning_predictions: u8,
+    pub outcome_attestation: [u8; 32],
+}
+
+#[event]
+pub struct RoundsMerged {
+    pub source_round_id: u64,
+    pub target_round_id: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RoundCancelled {
+    pub round_id: u64,
+}
+
+#[event]
+pub struct RoundClosed {
+    pub round_id: u64,
+    pub final_escrow_swept: u64,
+    pub rent_reclaimed: u64,
+}
+
+#[event]
+pub struct TreasuryRefunded {
+    pub round_id: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PayoutClaimed {
+    pub round_id: u64,
+    pub bettor: Pubkey,
+    pub payout_amount: u64, // CHANGED: Just the equal share amount
+    pub token_mint: Pubkey,
+    pub decimals: u8,
+    pub prediction: BossPrediction,
+    pub prediction_code: u8,
+}
+
+#[event]
+pub struct UnclaimedRedistributed {
+    pub round_id: u64,
+    pub bonus_pool: u64,
+    pub claimed_winners: u64,
+    pub bonus_per_winner: u64,
+}
+
+#[event]
+pub struct BonusClaimed {
+    pub round_id: u64,
+    pub bettor: Pubkey,
+    pub bonus_amount: u64,
+}
+
+/// The line `claim_require!` logs before a claim error
+fn claim_failure_breadcrumb(betting_round: &BettingRound, bet_account: &BetAccount) -> String {
+    format!(
+        "claim failed: phase={:?} won={} winners={}",
+        betting_round.phase,
+        betting_round.is_winning(&bet_account.prediction),
+        betting_round.total_winners()
+    )
+}
+
+/// `require!` for `claim_payout` that first logs the round's state, so a failed claim's
+/// transaction logs say why rather than just the error code
+macro_rules! claim_require {
+    ($round:expr, $bet:expr, $cond:expr, $err:expr $(,)?) => {
+        if !($cond) {
+            msg!("{}", claim_failure_breadcrumb(&$round, &$bet));
+            return Err(error!($err));
+        }
+    };
+}
+
+// =================================================================
+// ⭐️ PROGRAM INSTRUCTIONS ⭐️
+// =================================================================
+#[program]
+pub mod boss_fight_betting {
+    use super::*;
+
+    /// Create the operator's lifetime stats account
+    pub fn initialize_global_stats(
+        ctx: Context<InitializeGlobalStats>,
+        round_cooldown: i64,
+    ) -> Result<()> {
+        let global_stats = &mut ctx.accounts.global_stats;
+
+        require!(round_cooldown >= 0, BettingError::InvalidCooldown);
+
+        global_stats.authority = ctx.accounts.authority.key();
+        global_stats.rounds_created = 0;
+        global_stats.total_pool_funded = 0;
+        global_stats.total_paid_out = 0;
+        global_stats.round_cooldown = round_cooldown;
+        global_stats.last_round_created_at = 0;
+        global_stats.bump = ctx.bumps.global_stats;
+        global_stats.max_concurrent_rounds = 0;
+        global_stats.open_rounds = 0;
+        global_stats.min_initial_hp = 0;
+
+        Ok(())
+    }
+
+    /// Change the minimum time between rounds created by this authority
+    pub fn set_round_cooldown(ctx: Context<UpdateGlobalStats>, round_cooldown: i64) -> Result<()> {
+        require!(round_cooldown >= 0, BettingError::InvalidCooldown);
+
+        ctx.accounts.global_stats.round_cooldown = round_cooldown;
+
+        Ok(())
+    }
+
+    /// Cap how many of this authority's rounds may be open at once (0 = unlimited)
+    pub fn set_max_concurrent_rounds(
+        ctx: Context<UpdateGlobalStats>,
+        max_concurrent_rounds: u64,
+    ) -> Result<()> {
+        ctx.accounts.global_stats.max_concurrent_rounds = max_concurrent_rounds;
+
+        Ok(())
+    }
+
+    /// Set the lowest starting boss HP allowed for this authority's rounds
+    pub fn set_min_initial_hp(ctx: Context<UpdateGlobalStats>, min_initial_hp: u32) -> Result<()> {
+        ctx.accounts.global_stats.min_initial_hp = min_initial_hp;
+
+        Ok(())
+    }
+
+    /// Create a player's cross-round stats account
+    pub fn initialize_user_profile(ctx: Context<InitializeUserProfile>) -> Result<()> {
+        let user_profile = &mut ctx.accounts.user_profile;
+
+        user_profile.bettor = ctx.accounts.bettor.key();
+        user_profile.bets_placed = 0;
+        user_profile.bets_won = 0;
+        user_profile.total_won = 0;
+        user_profile.bump = ctx.bumps.user_profile;
+
+        Ok(())
+    }
+
+    /// Create the optional recent-winners board for a round
+    pub fn initialize_winners_board(ctx: Context<InitializeWinnersBoard>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.betting_round.authority,
+            BettingError::Unauthorized
+        );
+
+        let winners_board = &mut ctx.accounts.winners_board;
+        winners_board.round_id = ctx.accounts.betting_round.round_id;
+        winners_board.entries = Vec::new();
+        winners_board.bump = ctx.bumps.winners_board;
+
+        Ok(())
+    }
+
+    /// Create the keeper-facing claim bitmap for a round; do this before betting opens
+    pub fn initialize_claim_bitmap(ctx: Context<InitializeClaimBitmap>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.betting_round.authority,
+            BettingError::Unauthorized
+        );
+        // Bets placed before the bitmap existed would never be indexed in it
+        require!(
+            ctx.accounts.betting_round.total_bets_count == 0,
+            BettingError::BetsAlreadyPlaced
+        );
+
+        let claim_bitmap = &mut ctx.accounts.claim_bitmap;
+        claim_bitmap.round_id = ctx.accounts.betting_round.round_id;
+        claim_bitmap.death_bets = [0u8; CLAIM_BITMAP_BYTES];
+        claim_bitmap.claimed = [0u8; CLAIM_BITMAP_BYTES];
+        claim_bitmap.bump = ctx.bumps.claim_bitmap;
+        ctx.accounts.betting_round.claim_bitmap_enabled = true;
+
+        Ok(())
+    }
+
+    /// Create the claimed-leaf bitmap for a merkle round; needed before the first merkle claim
+    pub fn initialize_merkle_claims(ctx: Context<InitializeMerkleClaims>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            ctx.accounts.betting_round.config.merkle_bets,
+            BettingError::NotMerkleRound
+        );
+
+        let mut merkle_claims = ctx.accounts.merkle_claims.load_init()?;
+        merkle_claims.round_id = ctx.accounts.betting_round.round_id;
+        merkle_claims.bump = ctx.bumps.merkle_claims;
+
+        Ok(())
+    }
+
+    /// Commit a merkle round's off-chain bets once betting closes: the tree root plus the
+    /// per-side tallies the payout split is computed from. May be re-committed until the
+    /// fight starts.
+    pub fn commit_merkle_bets(
+        ctx: Context<CommitMerkleBets>,
+        bets_merkle_root: [u8; 32],
+        death_bets: u64,
+        survival_bets: u64,
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.config.merkle_bets,
+            BettingError::NotMerkleRound
+        );
+        require!(
+            betting_round.phase == GamePhase::Betting,
+            BettingError::NotInBettingPhase
+        );
+        require!(
+            clock.unix_timestamp >= betting_round.betting_close_time()?,
+            BettingError::BettingStillActive
+        );
+
+        let total_bets = death_bets
+            .checked_add(survival_bets)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        // Every leaf needs a bit in the round's MerkleClaims
+        require!(
+            total_bets <= MERKLE_CLAIMS_CAPACITY,
+            BettingError::MerkleIndexOutOfRange
+        );
+
+        betting_round.bets_merkle_root = bets_merkle_root;
+        betting_round.total_death_bets = death_bets;
+        betting_round.total_survival_bets = survival_bets;
+        betting_round.total_bets_count = total_bets;
+        betting_round.merkle_bets_committed = true;
+
+        emit!(MerkleBetsCommitted {
+            round_id: betting_round.round_id,
+            bets_merkle_root,
+            death_bets,
+            survival_bets,
+        });
+
+        Ok(())
+    }
+
+    /// Replace a round's blocklist before betting ends; an empty list turns it off
+    pub fn set_blocklist(ctx: Context<SetBlocklist>, blocked: Vec<Pubkey>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            matches!(betting_round.phase, GamePhase::Funding | GamePhase::Betting),
+            BettingError::NotInBettingPhase
+        );
+        require!(
+            blocked.len() <= BLOCKLIST_CAPACITY,
+            BettingError::InvalidBatchSize
+        );
+
+        betting_round.blocklist_enabled = !blocked.is_empty();
+
+        let blocklist = &mut ctx.accounts.blocklist;
+        blocklist.round_id = betting_round.round_id;
+        blocklist.blocked = blocked;
+        blocklist.bump = ctx.bumps.blocklist;
+
+        Ok(())
+    }
+
+    /// Initialize a new betting round with treasury-funded prize pool
+    pub fn initialize_betting_round(
+        ctx: Context<InitializeBettingRound>,
+        round_id: u64,
+        betting_duration: i64,
+        fight_duration: i64,
+        initial_hp: u32,
+        prize_pool_amount: u64, // NEW: Treasury funds this amount
+        config: RoundConfig,
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = Clock::get()?;
+
+        betting_round.open(
+            RoundParams {
+                round_id,
+                betting_duration,
+                fight_duration,
+                initial_hp,
+                prize_pool_amount,
+                config,
+            },
+            ctx.accounts.authority.key(),
+            ctx.accounts.treasury.key(),
+            &ctx.accounts.token_mint,
+            ctx.bumps.escrow_token_account,
+            clock.unix_timestamp,
+        )?;
+        betting_round.escrow = ctx.accounts.escrow_token_account.key();
+
+        // Transfer prize pool from treasury to escrow
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            prize_pool_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Transfer-fee mints deliver less than was sent, so the pool is what actually landed
+        ctx.accounts.escrow_token_account.reload()?;
+        let prize_pool_amount = ctx.accounts.escrow_token_account.amount;
+        betting_round.prize_pool_amount = prize_pool_amount;
+
+        ctx.accounts.global_stats.record_round_created(
+            clock.unix_timestamp,
+            prize_pool_amount,
+            betting_round.initial_hp,
+        )?;
+        betting_round.counted_as_open = true;
+
+        emit!(BettingRoundInitialized {
+            round_id,
+            betting_end_time: betting_round.betting_end_time,
+            fight_end_time: betting_round.fight_end_time,
+            token_mint: betting_round.token_mint,
+            prize_pool_amount,
+            decimals: betting_round.token_decimals,
+            round_kind: betting_round.config.round_kind.clone(),
+            rules_hash: betting_round.config.rules_hash,
+            game_name: betting_round.config.game_name.clone(),
+        });
+
+        emit!(EscrowBalanceChanged {
+            round_id,
+            new_balance: prize_pool_amount,
+            reason: EscrowChangeReason::Funded,
+        });
+        check_escrow_invariant(betting_round, prize_pool_amount)?;
+
+        Ok(())
+    }
+
+    /// Create and fund `count` rounds with ids `base_round_id..base_round_id + count` and
+    /// shared parameters. `remaining_accounts` holds one (betting_round, escrow) PDA pair per
+    /// round. The series counts as a single creation against the authority's cooldown.
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_round_series<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitializeRoundSeries<'info>>,
+        base_round_id: u64,
+        count: u8,
+        betting_duration: i64,
+        fight_duration: i64,
+        initial_hp: u32,
+        prize_pool_amount: u64,
+        config: RoundConfig,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let authority = ctx.accounts.authority.key();
+        let token_program_id = ctx.accounts.token_program.key();
+
+        require!(
+            count > 0 && count <= MAX_ROUND_SERIES,
+            BettingError::InvalidBatchSize
+        );
+        require!(
+            ctx.remaining_accounts.len() == count as usize * 2,
+            BettingError::InvalidBatchSize
+        );
+
+        let global_stats = &ctx.accounts.global_stats;
+        global_stats.check_round_creation(clock.unix_timestamp, initial_hp)?;
+        let open_after = global_stats
+            .open_rounds
+            .checked_add(count as u64)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        require!(
+            global_stats.max_concurrent_rounds == 0
+                || open_after <= global_stats.max_concurrent_rounds,
+            BettingError::TooManyOpenRounds
+        );
+
+        let escrow_space = token_account_space(&ctx.accounts.token_mint)?;
+
+        for (offset, pair) in ctx.remaining_accounts.chunks(2).enumerate() {
+            let round_info = &pair[0];
+            let escrow_info = &pair[1];
+            let round_id = base_round_id
+                .checked_add(offset as u64)
+                .ok_or(BettingError::ArithmeticOverflow)?;
+            let round_id_bytes = round_id.to_le_bytes();
+
+            let (expected_round, round_bump) = Pubkey::find_program_address(
+                &[
+                    b"betting_round",
+                    authority.as_ref(),
+                    round_id_bytes.as_ref(),
+                ],
+                ctx.program_id,
+            );
+            let (expected_escrow, escrow_bump) = Pubkey::find_program_address(
+                &[b"escrow", authority.as_ref(), round_id_bytes.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(
+                round_info.key(),
+                expected_round,
+                BettingError::InvalidAccount
+            );
+            require_keys_eq!(
+                escrow_info.key(),
+                expected_escrow,
+                BettingError::InvalidAccount
+            );
+            require!(
+                round_info.data_is_empty() && escrow_info.data_is_empty(),
+                BettingError::RoundAlreadyExists
+            );
+
+            create_pda_account(
+                round_info,
+                &ctx.accounts.authority,
+                &ctx.accounts.system_program,
+                8 + BettingRound::INIT_SPACE,
+                &crate::ID,
+                &[
+                    b"betting_round",
+                    authority.as_ref(),
+                    round_id_bytes.as_ref(),
+                    &[round_bump],
+                ],
+            )?;
+            create_pda_account(
+                escrow_info,
+                &ctx.accounts.authority,
+                &ctx.accounts.system_program,
+                escrow_space,
+                &token_program_id,
+                &[
+                    b"escrow",
+                    authority.as_ref(),
+                    round_id_bytes.as_ref(),
+                    &[escrow_bump],
+                ],
+            )?;
+            token_interface::initialize_account3(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::InitializeAccount3 {
+                    account: escrow_info.clone(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    authority: escrow_info.clone(),
+                },
+            ))?;
+
+            // Freshly allocated data is all zeroes; `open` fills in every field
+            let mut betting_round: Account<'info, BettingRound> =
+                Account::try_from_unchecked(round_info)?;
+            let mut escrow_token_account: InterfaceAccount<'info, TokenAccount> =
+                InterfaceAccount::try_from(escrow_info)?;
+
+            betting_round.open(
+                RoundParams {
+                    round_id,
+                    betting_duration,
+                    fight_duration,
+                    initial_hp,
+                    prize_pool_amount,
+                    config: config.clone(),
+                },
+                authority,
+                ctx.accounts.treasury.key(),
+                &ctx.accounts.token_mint,
+                escrow_bump,
+                clock.unix_timestamp,
+            )?;
+            betting_round.escrow = escrow_info.key();
+
+            token_interface::transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.treasury_token_account.to_account_info(),
+                        mint: ctx.accounts.token_mint.to_account_info(),
+                        to: escrow_token_account.to_account_info(),
+                        authority: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                prize_pool_amount,
+                ctx.accounts.token_mint.decimals,
+            )?;
+
+            // Transfer-fee mints deliver less than was sent, so the pool is what actually landed
+            escrow_token_account.reload()?;
+            let funded_amount = escrow_token_account.amount;
+            betting_round.prize_pool_amount = funded_amount;
+
+            ctx.accounts
+                .global_stats
+                .count_round_created(clock.unix_timestamp, funded_amount)?;
+            betting_round.counted_as_open = true;
+
+            emit!(BettingRoundInitialized {
+                round_id,
+                betting_end_time: betting_round.betting_end_time,
+                fight_end_time: betting_round.fight_end_time,
+                token_mint: betting_round.token_mint,
+                prize_pool_amount: funded_amount,
+                decimals: betting_round.token_decimals,
+                round_kind: betting_round.config.round_kind.clone(),
+                rules_hash: betting_round.config.rules_hash,
+                game_name: betting_round.config.game_name.clone(),
+            });
+
+            emit!(EscrowBalanceChanged {
+                round_id,
+                new_balance: funded_amount,
+                reason: EscrowChangeReason::Funded,
+            });
+            check_escrow_invariant(&betting_round, funded_amount)?;
+
+            betting_round.exit(ctx.program_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Initialize a new betting round funded from the authority's program-controlled treasury
+    /// PDA (`[b"treasury", authority]`), for operators whose treasury can't sign directly
+    pub fn initialize_betting_round_pda_treasury(
+        ctx: Context<InitializeBettingRoundPdaTreasury>,
+        round_id: u64,
+        betting_duration: i64,
+        fight_duration: i64,
+        initial_hp: u32,
+        prize_pool_amount: u64,
+        config: RoundConfig,
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = Clock::get()?;
+
+        betting_round.open(
+            RoundParams {
+                round_id,
+                betting_duration,
+                fight_duration,
+                initial_hp,
+                prize_pool_amount,
+                config,
+            },
+            ctx.accounts.authority.key(),
+            ctx.accounts.treasury.key(),
+            &ctx.accounts.token_mint,
+            ctx.bumps.escrow_token_account,
+            clock.unix_timestamp,
+        )?;
+        betting_round.escrow = ctx.accounts.escrow_token_account.key();
+
+        // Transfer prize pool from the treasury PDA to escrow
+        let authority_key = ctx.accounts.authority.key();
+        let treasury_seeds: &[&[u8]] =
+            &[b"treasury", authority_key.as_ref(), &[ctx.bumps.treasury]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+                &[treasury_seeds],
+            ),
+            prize_pool_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Transfer-fee mints deliver less than was sent, so the pool is what actually landed
+        ctx.accounts.escrow_token_account.reload()?;
+        let prize_pool_amount = ctx.accounts.escrow_token_account.amount;
+        betting_round.prize_pool_amount = prize_pool_amount;
+
+        ctx.accounts.global_stats.record_round_created(
+            clock.unix_timestamp,
+            prize_pool_amount,
+            betting_round.initial_hp,
+        )?;
+        betting_round.counted_as_open = true;
+
+        emit!(BettingRoundInitialized {
+            round_id,
+            betting_end_time: betting_round.betting_end_time,
+            fight_end_time: betting_round.fight_end_time,
+            token_mint: betting_round.token_mint,
+            prize_pool_amount,
+            decimals: betting_round.token_decimals,
+            round_kind: betting_round.config.round_kind.clone(),
+            rules_hash: betting_round.config.rules_hash,
+            game_name: betting_round.config.game_name.clone(),
+        });
+
+        emit!(EscrowBalanceChanged {
+            round_id,
+            new_balance: prize_pool_amount,
+            reason: EscrowChangeReason::Funded,
+        });
+        check_escrow_invariant(betting_round, prize_pool_amount)?;
+
+        Ok(())
+    }
+
+    /// Create a new round with the mint, HP and config of an existing round by the same authority
+    pub fn clone_round(
+        ctx: Context<CloneRound>,
+        round_id: u64,
+        betting_duration: i64,
+        fight_duration: i64,
+        prize_pool_amount: u64,
+    ) -> Result<()> {
+        let source_round = &ctx.accounts.source_round;
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = Clock::get()?;
+
+        require!(
+            source_round.authority == ctx.accounts.authority.key(),
+            BettingError::Unauthorized
+        );
+
+        betting_round.open(
+            RoundParams {
+                round_id,
+                betting_duration,
+                fight_duration,
+                initial_hp: source_round.initial_hp,
+                prize_pool_amount,
+                config: source_round.config.clone(),
+            },
+            ctx.accounts.authority.key(),
+            ctx.accounts.treasury.key(),
+            &ctx.accounts.token_mint,
+            ctx.bumps.escrow_token_account,
+            clock.unix_timestamp,
+        )?;
+        betting_round.escrow = ctx.accounts.escrow_token_account.key();
+
+        // Transfer prize pool from treasury to escrow
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            prize_pool_amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Transfer-fee mints deliver less than was sent, so the pool is what actually landed
+        ctx.accounts.escrow_token_account.reload()?;
+        let prize_pool_amount = ctx.accounts.escrow_token_account.amount;
+        betting_round.prize_pool_amount = prize_pool_amount;
+
+        ctx.accounts.global_stats.record_round_created(
+            clock.unix_timestamp,
+            prize_pool_amount,
+            betting_round.initial_hp,
+        )?;
+        betting_round.counted_as_open = true;
+
+        emit!(BettingRoundInitialized {
+            round_id,
+            betting_end_time: betting_round.betting_end_time,
+            fight_end_time: betting_round.fight_end_time,
+            token_mint: betting_round.token_mint,
+            prize_pool_amount,
+            decimals: betting_round.token_decimals,
+            round_kind: betting_round.config.round_kind.clone(),
+            rules_hash: betting_round.config.rules_hash,
+            game_name: betting_round.config.game_name.clone(),
+        });
+
+        emit!(EscrowBalanceChanged {
+            round_id,
+            new_balance: prize_pool_amount,
+            reason: EscrowChangeReason::Funded,
+        });
+        check_escrow_invariant(betting_round, prize_pool_amount)?;
+
+        Ok(())
+    }
+
+    /// Add tokens to a crowdfunded round's prize pool before betting opens
+    pub fn contribute_to_pool(ctx: Context<ContributeToPool>, amount: u64) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            betting_round.config.prize_pool_source == PrizePoolSource::Crowdfunded,
+            BettingError::NotCrowdfunded
+        );
+        require!(
+            betting_round.phase == GamePhase::Funding,
+            BettingError::FundingClosed
+        );
+        require!(
+            clock.unix_timestamp < betting_round.funding_end_time,
+            BettingError::FundingClosed
+        );
+        require!(amount > 0, BettingError::InvalidAmount);
+
+        let escrow_before = ctx.accounts.escrow_token_account.amount;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.contributor_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.contributor.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Credit what actually landed, in case the mint charges a transfer fee
+        ctx.accounts.escrow_token_account.reload()?;
+        let new_balance = ctx.accounts.escrow_token_account.amount;
+        let received = new_balance
+            .checked_sub(escrow_before)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        betting_round.prize_pool_amount = betting_round
+            .prize_pool_amount
+            .checked_add(received)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        betting_round.total_contributed = betting_round
+            .total_contributed
+            .checked_add(received)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        let contribution = &mut ctx.accounts.contribution;
+        contribution.round_id = betting_round.round_id;
+        contribution.contributor = ctx.accounts.contributor.key();
+        contribution.amount = contribution
+            .amount
+            .checked_add(received)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        contribution.bump = ctx.bumps.contribution;
+
+        emit!(PoolContribution {
+            round_id: betting_round.round_id,
+            contributor: ctx.accounts.contributor.key(),
+            amount: received,
+            prize_pool_amount: betting_round.prize_pool_amount,
+        });
+
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance,
+            reason: EscrowChangeReason::Contribution,
+        });
+        check_escrow_invariant(betting_round, new_balance)?;
+
+        Ok(())
+    }
+
+    /// Add an installment from the round's (signing) treasury towards its `target_prize_pool`.
+    /// Allowed until the fight starts.
+    pub fn add_installment(ctx: Context<AddInstallment>, amount: u64) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            betting_round.config.target_prize_pool > 0,
+            BettingError::InstallmentsDisabled
+        );
+        require!(
+            betting_round.phase == GamePhase::Funding || betting_round.phase == GamePhase::Betting,
+            BettingError::FundingClosed
+        );
+        require!(amount > 0, BettingError::InvalidAmount);
+
+        let escrow_before = ctx.accounts.escrow_token_account.amount;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Credit what actually landed, in case the mint charges a transfer fee
+        ctx.accounts.escrow_token_account.reload()?;
+        let new_balance = ctx.accounts.escrow_token_account.amount;
+        let received = new_balance
+            .checked_sub(escrow_before)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        betting_round.prize_pool_amount = betting_round
+            .prize_pool_amount
+            .checked_add(received)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        emit!(InstallmentAdded {
+            round_id: betting_round.round_id,
+            amount: received,
+            prize_pool_amount: betting_round.prize_pool_amount,
+            target_prize_pool: betting_round.config.target_prize_pool,
+        });
+
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance,
+            reason: EscrowChangeReason::Funded,
+        });
+        check_escrow_invariant(betting_round, new_balance)?;
+
+        Ok(())
+    }
+
+    /// Place a bet on boss death or survival (NO TOKENS REQUIRED)
+    pub fn place_bet(
+        ctx: Context<PlaceBet>,
+        prediction: BossPrediction,
+        username: String,
+        memo: String,
+    ) -> Result<()> {
+        let clock = round_clock(&ctx.accounts.betting_round)?;
+
+        // Explicit signer check
+        require!(ctx.accounts.bettor.is_signer, BettingError::Unauthorized);
+        ctx.accounts
+            .betting_round
+            .check_bet_slot(&ctx.accounts.bet_account)?;
+
+        if ctx.accounts.betting_round.blocklist_enabled {
+            let blocklist = ctx
+                .accounts
+                .blocklist
+                .as_ref()
+                .ok_or(BettingError::BlocklistRequired)?;
+            require!(
+                !blocklist.blocked.contains(&ctx.accounts.bettor.key()),
+                BettingError::BettorBlocked
+            );
+        }
+
+        ctx.accounts.betting_round.accept_bet(
+            &mut ctx.accounts.bet_account,
+            ctx.accounts.bettor.key(),
+            ctx.accounts.payer.key(),
+            prediction,
+            username,
+            clock.unix_timestamp,
+        )?;
+        ctx.accounts.bet_account.round = ctx.accounts.betting_round.key();
+
+        validate_memo(&memo)?;
+        ctx.accounts.bet_account.memo = memo;
+
+        if ctx.accounts.betting_round.config.validate_mint_on_bet {
+            let token_mint = ctx
+                .accounts
+                .token_mint
+                .as_ref()
+                .ok_or(BettingError::InvalidTokenMint)?;
+            require!(
+                mint_can_pay_out(token_mint)?,
+                BettingError::InvalidTokenMint
+            );
+        }
+
+        if let Some(user_profile) = ctx.accounts.user_profile.as_mut() {
+            user_profile.bets_placed = user_profile
+                .bets_placed
+                .checked_add(1)
+                .ok_or(BettingError::ArithmeticOverflow)?;
+        }
+
+        if let Some(claim_bitmap) = ctx.accounts.claim_bitmap.as_mut() {
+            let bet_account = &ctx.accounts.bet_account;
+            claim_bitmap.record_bet(bet_account.bet_index, &bet_account.prediction);
+        } else {
+            require!(
+                !ctx.accounts.betting_round.claim_bitmap_enabled,
+                BettingError::ClaimBitmapRequired
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Place bets on several rounds atomically. `remaining_accounts` holds one
+    /// (betting_round, bet_account) pair per entry in `bets`, in the same order;
+    /// any invalid bet reverts the whole batch.
+    pub fn place_bets_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceBetsBatch<'info>>,
+        bets: Vec<BatchBet>,
+    ) -> Result<()> {
+        let bettor = ctx.accounts.bettor.key();
+        let payer = ctx.accounts.payer.key();
+
+        require!(
+            !bets.is_empty() && bets.len() <= MAX_BATCH_BETS,
+            BettingError::InvalidBatchSize
+        );
+        require!(
+            ctx.remaining_accounts.len() == bets.len() * 2,
+            BettingError::InvalidBatchSize
+        );
+
+        for (bet, pair) in bets.into_iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let round_info = &pair[0];
+            let bet_info = &pair[1];
+
+            require!(round_info.is_writable, BettingError::InvalidAccount);
+            let mut betting_round: Account<'info, BettingRound> = Account::try_from(round_info)?;
+            // Batches carry no blocklist, bitmap or mint accounts; rounds needing them take
+            // bets via place_bet
+            require!(
+                !betting_round.blocklist_enabled,
+                BettingError::BlocklistRequired
+            );
+            require!(
+                !betting_round.claim_bitmap_enabled,
+                BettingError::ClaimBitmapRequired
+            );
+            require!(
+                !betting_round.config.validate_mint_on_bet,
+                BettingError::InvalidTokenMint
+            );
+            // Each bet is stamped from its own round's clock, never from the batch payload
+            let clock = round_clock(&betting_round)?;
+
+            let round_id_bytes = betting_round.round_id.to_le_bytes();
+            let (expected_bet, bet_bump) = Pubkey::find_program_address(
+                &[
+                    b"bet",
+                    betting_round.authority.as_ref(),
+                    round_id_bytes.as_ref(),
+                    bettor.as_ref(),
+                ],
+                ctx.program_id,
+            );
+            require_keys_eq!(bet_info.key(), expected_bet, BettingError::InvalidAccount);
+
+            create_pda_account(
+                bet_info,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                8 + BetAccount::INIT_SPACE,
+                &crate::ID,
+                &[
+                    b"bet",
+                    betting_round.authority.as_ref(),
+                    round_id_bytes.as_ref(),
+                    bettor.as_ref(),
+                    &[bet_bump],
+                ],
+            )?;
+
+            let mut bet_account = BetAccount {
+                bettor,
+                round_id: betting_round.round_id,
+                prediction: bet.prediction.clone(),
+                username: String::new(),
+                timestamp: 0,
+                payout_claimed: false,
+                bonus_claimed: false,
+                side_rank: 0,
+                rent_payer: payer,
+                betting_end_time_snapshot: 0,
+                bet_index: 0,
+                claimed_so_far: 0,
+                is_late: false,
+                memo: String::new(),
+                round: round_info.key(),
+                original_bettor: bettor,
+            };
+            betting_round.accept_bet(
+                &mut bet_account,
+                bettor,
+                payer,
+                bet.prediction,
+                bet.username,
+                clock.unix_timestamp,
+            )?;
+            validate_memo(&bet.memo)?;
+            bet_account.memo = bet.memo;
+
+            if let Some(user_profile) = ctx.accounts.user_profile.as_mut() {
+                user_profile.bets_placed = user_profile
+                    .bets_placed
+                    .checked_add(1)
+                    .ok_or(BettingError::ArithmeticOverflow)?;
+            }
+
+            bet_account.try_serialize(&mut &mut bet_info.try_borrow_mut_data()?[..])?;
+            betting_round.exit(ctx.program_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fold one betting round's pool into another by the same authority and mint. The
+    /// drained round is cancelled so its bettors can close their bets as usual.
+    pub fn merge_rounds(ctx: Context<MergeRounds>) -> Result<()> {
+        let source_round = &mut ctx.accounts.source_round;
+        let target_round = &mut ctx.accounts.target_round;
+
+        require!(
+            ctx.accounts.authority.key() == source_round.authority
+                && ctx.accounts.authority.key() == target_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            [&source_round.phase, &target_round.phase]
+                .iter()
+                .all(|phase| matches!(phase, GamePhase::Funding | GamePhase::Betting)),
+            BettingError::NotInBettingPhase
+        );
+        require!(
+            source_round.token_mint == target_round.token_mint,
+            BettingError::InvalidTokenMint
+        );
+        require!(
+            !source_round.pool_locked() && !target_round.pool_locked(),
+            BettingError::PoolLocked
+        );
+        // Merging would cancel the source and move the contributors' deposits out of reach
+        require!(
+            source_round.total_contributed == 0,
+            BettingError::ContributionsHeld
+        );
+
+        let amount = ctx.accounts.source_escrow_token_account.amount;
+        let target_before = ctx.accounts.target_escrow_token_account.amount;
+        transfer_from_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.source_escrow_token_account,
+            &ctx.accounts.token_mint,
+            ctx.accounts.target_escrow_token_account.to_account_info(),
+            source_round,
+            ctx.bumps.source_escrow_token_account,
+            amount,
+        )?;
+
+        // Credit what actually landed, in case the mint charges a transfer fee
+        ctx.accounts.target_escrow_token_account.reload()?;
+        let new_balance = ctx.accounts.target_escrow_token_account.amount;
+        let received = new_balance
+            .checked_sub(target_before)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        target_round.prize_pool_amount = target_round
+            .prize_pool_amount
+            .checked_add(received)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        source_round.prize_pool_amount = 0;
+        source_round.phase = GamePhase::Cancelled;
+
+        emit!(RoundsMerged {
+            source_round_id: source_round.round_id,
+            target_round_id: target_round.round_id,
+            amount: received,
+        });
+        emit!(RoundCancelled {
+            round_id: source_round.round_id,
+        });
+        emit!(EscrowBalanceChanged {
+            round_id: source_round.round_id,
+            new_balance: 0,
+            reason: EscrowChangeReason::Merge,
+        });
+        check_escrow_invariant(source_round, 0)?;
+        emit!(EscrowBalanceChanged {
+            round_id: target_round.round_id,
+            new_balance,
+            reason: EscrowChangeReason::Merge,
+        });
+        check_escrow_invariant(target_round, new_balance)?;
+
+        Ok(())
+    }
+
+    /// Stop (or resume) accepting new bets without affecting bets already placed
+    pub fn set_bets_frozen(ctx: Context<SetBetsFrozen>, frozen: bool) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            betting_round.phase == GamePhase::Betting,
+            BettingError::NotInBettingPhase
+        );
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+
+        betting_round.bets_frozen = frozen;
+
+        emit!(BetsFrozenChanged {
+            round_id: betting_round.round_id,
+            bets_frozen: frozen,
+        });
+
+        Ok(())
+    }
+
+    /// Open claims on an ended round, e.g. once an off-chain verification period passes
+    pub fn open_claims(ctx: Context<OpenClaims>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.phase == GamePhase::Ended,
+            BettingError::FightNotEnded
+        );
+        require!(!betting_round.claims_open, BettingError::ClaimsAlreadyOpen);
+
+        betting_round.claims_open = true;
+
+        emit!(ClaimsOpened {
+            round_id: betting_round.round_id,
+        });
+
+        Ok(())
+    }
+
+    /// Pin the round's clock to `mock_time` (0 restores the real clock). Only exists in
+    /// `test-time` builds, for exercising deadlines without waiting on them.
+    #[cfg(feature = "test-time")]
+    pub fn set_mock_time(ctx: Context<SetMockTime>, mock_time: i64) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(mock_time >= 0, BettingError::InvalidDuration);
+
+        betting_round.mock_time = mock_time;
+
+        Ok(())
+    }
+
+    /// Hand the fight-running role to another wallet
+    pub fn set_operator(ctx: Context<SetOperator>, operator: Pubkey) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(operator != Pubkey::default(), BettingError::InvalidAccount);
+
+        betting_round.operator = operator;
+
+        emit!(OperatorChanged {
+            round_id: betting_round.round_id,
+            operator,
+        });
+
+        Ok(())
+    }
+
+    /// Move a round out of its funding period and start taking bets
+    pub fn open_betting(ctx: Context<OpenBetting>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        betting_round.open_betting_at(clock.unix_timestamp)?;
+
+        emit!(BettingOpened {
+            round_id: betting_round.round_id,
+            prize_pool_amount: betting_round.prize_pool_amount,
+            betting_end_time: betting_round.betting_end_time,
+        });
+
+        Ok(())
+    }
+
+    /// Start the fighting phase, committing to the full bet set before the outcome is known
+    pub fn start_fight_phase(
+        ctx: Context<StartFightPhase>,
+        bets_merkle_root: [u8; 32],
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            betting_round.phase == GamePhase::Betting,
+            BettingError::NotInBettingPhase
+        );
+        // Late bets must be in before the bet set is committed
+        require!(
+            clock.unix_timestamp >= betting_round.betting_close_time()?,
+            BettingError::BettingStillActive
+        );
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+
+        require!(
+            betting_round.prize_pool_amount >= betting_round.config.target_prize_pool,
+            BettingError::PoolUnderfunded
+        );
+        // Off-chain rounds already committed the tree their tallies came from
+        if betting_round.config.merkle_bets {
+            require!(
+                betting_round.merkle_bets_committed,
+                BettingError::MerkleBetsNotCommitted
+            );
+            require!(
+                bets_merkle_root == betting_round.bets_merkle_root,
+                BettingError::MerkleRootMismatch
+            );
+        }
+
+        betting_round.phase = GamePhase::Fighting;
+        betting_round.bets_merkle_root = bets_merkle_root;
+        betting_round.fight_start_time = clock.unix_timestamp;
+        // A late start still gets the full fight duration
+        betting_round.fight_end_time = clock
+            .unix_timestamp
+            .checked_add(betting_round.fight_duration)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        emit!(FightPhaseStarted {
+            round_id: betting_round.round_id,
+            fight_start_time: betting_round.fight_start_time,
+            fight_end_time: betting_round.fight_end_time,
+            bets_merkle_root,
+        });
+
+        Ok(())
+    }
+
+    /// Update boss HP. Accepted while `now <= fight_end_time`; ending the fight is allowed
+    /// from `now >= fight_end_time`, so the boundary second admits both.
+    pub fn update_boss_hp(ctx: Context<UpdateBossHp>, new_hp: u32) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.operator.key() == betting_round.operator,
+            BettingError::Unauthorized
+        );
+
+        betting_round.apply_hp(new_hp, clock.unix_timestamp)
+    }
+
+    /// Update boss HP as a fraction of initial HP, for engines that only know relative damage
+    pub fn update_boss_hp_pct(ctx: Context<UpdateBossHp>, hp_bps: u16) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.operator.key() == betting_round.operator,
+            BettingError::Unauthorized
+        );
+        let new_hp = betting_round.hp_from_bps(hp_bps)?;
+        betting_round.apply_hp(new_hp, clock.unix_timestamp)
+    }
+
+    /// Post a short live note for clients to show during the fight; control characters
+    /// are stripped
+    pub fn set_operator_note(ctx: Context<SetOperatorNote>, note: String) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            ctx.accounts.operator.key() == betting_round.operator,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.phase == GamePhase::Fighting,
+            BettingError::NotInFightPhase
+        );
+
+        let note: String = note.chars().filter(|c| !c.is_control()).collect();
+        require!(note.len() <= 64, BettingError::NoteTooLong);
+
+        betting_round.operator_note = note.clone();
+
+        emit!(OperatorNote {
+            round_id: betting_round.round_id,
+            note,
+        });
+
+        Ok(())
+    }
+
+    /// Extend an expired fight once when the boss is nearly dead
+    pub fn extend_fight(ctx: Context<ExtendFight>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            betting_round.phase == GamePhase::Fighting,
+            BettingError::NotInFightPhase
+        );
+        require!(
+            ctx.accounts.operator.key() == betting_round.operator,
+            BettingError::Unauthorized
+        );
+        require!(
+            !betting_round.sudden_death_used,
+            BettingError::SuddenDeathAlreadyUsed
+        );
+        require!(
+            clock.unix_timestamp >= betting_round.fight_end_time,
+            BettingError::FightNotFinished
+        );
+        // HP can't change after the window closes, so this is the HP at the original timeout
+        require!(
+            betting_round.sudden_death_eligible(),
+            BettingError::SuddenDeathIneligible
+        );
+
+        betting_round.sudden_death_used = true;
+        betting_round.fight_end_time = clock
+            .unix_timestamp
+            .checked_add(betting_round.config.sudden_death_duration)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        emit!(SuddenDeath {
+            round_id: betting_round.round_id,
+            current_hp: betting_round.current_hp,
+            fight_end_time: betting_round.fight_end_time,
+        });
+
+        Ok(())
+    }
+
+    /// End the fight and determine outcome, recording a hash of the authoritative game result.
+    /// `winning_predictions` is a bitmask of predictions that pay out; 0 derives the single
+    /// winner from the final HP. Before `fight_end_time` only a dead boss (final_hp 0) may end
+    /// the fight; from `fight_end_time` on, inclusive, any final HP may.
+    pub fn end_fight(
+        ctx: Context<EndFight>,
+        final_hp: u64,
+        outcome_attestation: [u8; 32],
+        winning_predictions: u8,
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.operator.key() == betting_round.operator,
+            BettingError::Unauthorized
+        );
+
+        betting_round.resolve(
+            final_hp,
+            outcome_attestation,
+            winning_predictions,
+            clock.unix_timestamp,
+        )?;
+        betting_round.check_funded(ctx.accounts.escrow_token_account.amount);
+
+        Ok(())
+    }
+
+    /// End the fight and pay winners in the same transaction. `remaining_accounts` holds one
+    /// (bet_account, bettor_token_account, rent_payer) triple per winner to pay; winners left
+    /// out claim for themselves as usual. Rounds that vest payouts can't be distributed, nor
+    /// can rounds whose claims aren't accepted right at resolution (claims not auto-opened,
+    /// or a cooldown or settlement delay still to run).
+    pub fn resolve_and_distribute<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolveAndDistribute<'info>>,
+        final_hp: u64,
+        outcome_attestation: [u8; 32],
+        winning_predictions: u8,
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.operator.key() == betting_round.operator,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.config.vesting_duration == 0,
+            BettingError::DistributionUnsupported
+        );
+        require!(
+            ctx.remaining_accounts.len().is_multiple_of(3)
+                && ctx.remaining_accounts.len() / 3 <= MAX_DISTRIBUTE_WINNERS,
+            BettingError::InvalidBatchSize
+        );
+
+        betting_round.resolve(
+            final_hp,
+            outcome_attestation,
+            winning_predictions,
+            clock.unix_timestamp,
+        )?;
+        betting_round.check_funded(ctx.accounts.escrow_token_account.amount);
+        // A voided round has nobody to pay
+        if betting_round.phase != GamePhase::Ended {
+            return Ok(());
+        }
+        // Paying here must pass the same round-level gates as claim_payout
+        require!(
+            betting_round.claimable_now(clock.unix_timestamp)?,
+            BettingError::ClaimsNotOpen
+        );
+
+        let escrow_balance = pay_winners(
+            betting_round,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.token_program,
+            ctx.accounts.claim_bitmap.as_deref_mut(),
+            ctx.remaining_accounts,
+            ctx.program_id,
+            false,
+            ctx.bumps.escrow_token_account,
+        )?;
+
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance: escrow_balance,
+            reason: EscrowChangeReason::Payout,
+        });
+        check_escrow_invariant(betting_round, escrow_balance)?;
+
+        Ok(())
+    }
+
+    /// After the claim deadline, push unclaimed winnings to the winners' associated token
+    /// accounts instead of leaving them in escrow. `remaining_accounts` holds one
+    /// (bet_account, bettor_ata, rent_payer) triple per bet, in ascending `bet_index` order
+    /// from `sweep_cursor`. Large rounds sweep over several calls, each continuing where the
+    /// last stopped; `payouts_processed` is set only once every paid winner has been paid in
+    /// full.
+    pub fn force_claim_remaining<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ForceClaimRemaining<'info>>,
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.phase == GamePhase::Ended,
+            BettingError::FightNotEnded
+        );
+        require!(
+            betting_round.claim_deadline > 0 && clock.unix_timestamp > betting_round.claim_deadline,
+            BettingError::ClaimWindowStillOpen
+        );
+        // The bonus split already counted these shares as leftover escrow
+        require!(
+            !betting_round.unclaimed_redistributed,
+            BettingError::AlreadyRedistributed
+        );
+        require!(
+            !betting_round.payouts_processed,
+            BettingError::SweepComplete
+        );
+        require!(
+            ctx.remaining_accounts.len().is_multiple_of(3)
+                && ctx.remaining_accounts.len() / 3 <= MAX_DISTRIBUTE_WINNERS,
+            BettingError::InvalidBatchSize
+        );
+
+        let escrow_balance = pay_winners(
+            betting_round,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.token_program,
+            ctx.accounts.claim_bitmap.as_deref_mut(),
+            ctx.remaining_accounts,
+            ctx.program_id,
+            true,
+            ctx.bumps.escrow_token_account,
+        )?;
+
+        if betting_round.outstanding_obligation == 0 {
+            betting_round.payouts_processed = true;
+        }
+
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance: escrow_balance,
+            reason: EscrowChangeReason::Payout,
+        });
+        check_escrow_invariant(betting_round, escrow_balance)?;
+
+        Ok(())
+    }
+
+    /// Correct a mistaken `end_fight` result within `AMEND_WINDOW`; locked as soon as any
+    /// winner has claimed
+    pub fn amend_outcome(
+        ctx: Context<AmendOutcome>,
+        final_hp: u64,
+        outcome_attestation: [u8; 32],
+        winning_predictions: u8,
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.phase == GamePhase::Ended,
+            BettingError::FightNotEnded
+        );
+        // Any payment, including a partial vested claim, locks the outcome; so does time,
+        // since losing bets may be closed once the outcome is final
+        require!(
+            !betting_round.outcome_final(clock.unix_timestamp)?,
+            BettingError::ClaimsStarted
+        );
+
+        let current_hp = u32::try_from(final_hp).map_err(|_| BettingError::ArithmeticOverflow)?;
+        let boss_dead = final_hp == 0;
+        require!(
+            boss_dead || clock.unix_timestamp >= betting_round.fight_end_time,
+            BettingError::FightNotFinished
+        );
+        let winning_predictions = BossPrediction::outcome_mask(boss_dead, winning_predictions)?;
+        betting_round.check_winning_sides(winning_predictions)?;
+
+        let old_boss_defeated = betting_round.boss_defeated;
+        let old_winning_predictions = betting_round.winning_predictions;
+
+        betting_round.current_hp = current_hp;
+        betting_round.boss_defeated = boss_dead;
+        betting_round.winning_prediction = BossPrediction::outcome(boss_dead);
+        betting_round.winning_predictions = winning_predictions;
+        betting_round.outcome_attestation = outcome_attestation;
+        betting_round.outstanding_obligation = betting_round.total_obligation()?;
+        betting_round.check_funded(ctx.accounts.escrow_token_account.amount);
+
+        emit!(OutcomeAmended {
+            round_id: betting_round.round_id,
+            old_boss_defeated,
+            new_boss_defeated: boss_dead,
+            old_winning_predictions,
+            new_winning_predictions: winning_predictions,
+            outcome_attestation,
+        });
+
+        Ok(())
+    }
+
+    /// View: a round's headline state, including whether claims are accepted right now
+    pub fn round_summary(ctx: Context<RoundView>) -> Result<RoundSummary> {
+        let betting_round = &ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        Ok(RoundSummary {
+            phase: betting_round.phase.clone(),
+            current_hp: betting_round.current_hp,
+            initial_hp: betting_round.initial_hp,
+            prize_pool_amount: betting_round.prize_pool_amount,
+            total_bets_count: betting_round.total_bets_count,
+            total_claimed_count: betting_round.total_claimed_count,
+            claim_deadline: betting_round.claim_deadline,
+            claimable_now: betting_round.claimable_now(clock.unix_timestamp)?,
+        })
+    }
+
+    /// View: current bet split between the two sides, in basis points
+    pub fn current_odds(ctx: Context<RoundView>) -> Result<Odds> {
+        Ok(ctx.accounts.betting_round.odds())
+    }
+
+    /// View: run every creation-time check on a prospective round without creating it,
+    /// returning the schedule it would get or the first failing check's error
+    pub fn validate_round_params(
+        ctx: Context<ValidateRoundParams>,
+        betting_duration: i64,
+        fight_duration: i64,
+        initial_hp: u32,
+        prize_pool_amount: u64,
+        config: RoundConfig,
+    ) -> Result<RoundSchedule> {
+        let clock = Clock::get()?;
+        let params = RoundParams {
+            round_id: 0,
+            betting_duration,
+            fight_duration,
+            initial_hp,
+            prize_pool_amount,
+            config,
+        };
+
+        params.validate()?;
+        if let Some(global_stats) = ctx.accounts.global_stats.as_ref() {
+            global_stats.check_round_creation(clock.unix_timestamp, initial_hp)?;
+        }
+        // Treasury-funded rounds pull the whole pool at creation
+        if params.config.prize_pool_source == PrizePoolSource::Treasury {
+            require!(
+                ctx.accounts.treasury_token_account.amount >= prize_pool_amount,
+                BettingError::InsufficientTreasuryFunds
+            );
+        }
+
+        params.schedule(clock.unix_timestamp)
+    }
+
+    /// View: whether a bet is included in the round's committed bets merkle root
+    pub fn verify_bet_inclusion(
+        ctx: Context<VerifyBetInclusion>,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<bool> {
+        let leaf = ctx.accounts.bet_account.merkle_leaf();
+
+        Ok(verify_merkle_proof(
+            leaf,
+            &proof,
+            ctx.accounts.betting_round.bets_merkle_root,
+        ))
+    }
+
+    /// View: whether a bet won and what it could claim right now (zeroes until the round ends)
+    pub fn bet_result(ctx: Context<BetView>) -> Result<BetResult> {
+        let betting_round = &ctx.accounts.betting_round;
+        let bet_account = &ctx.accounts.bet_account;
+        let clock = round_clock(betting_round)?;
+
+        let won = betting_round.phase == GamePhase::Ended
+            && betting_round.is_winning(&bet_account.prediction);
+        let claimable = if won
+            && betting_round.claimable_now(clock.unix_timestamp)?
+            && !bet_account.payout_claimed
+            && betting_round.is_paid_rank(bet_account.side_rank)
+        {
+            let share = betting_round.bet_share(bet_account)?;
+            betting_round
+                .vested_amount(share, clock.unix_timestamp)?
+                .saturating_sub(bet_account.claimed_so_far)
+        } else {
+            0
+        };
+
+        Ok(BetResult {
+            won,
+            claimable,
+            already_claimed: bet_account.payout_claimed,
+        })
+    }
+
+    /// Claim equal share of prize pool for winning bet. Every claim writes the round's shared
+    /// counters, so concurrent claims serialize on the round account rather than race.
+    pub fn claim_payout(ctx: Context<ClaimPayout>, unwrap_sol: bool) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let bet_account = &mut ctx.accounts.bet_account;
+        let clock = round_clock(betting_round)?;
+
+        claim_require!(
+            betting_round,
+            bet_account,
+            betting_round.phase == GamePhase::Ended,
+            BettingError::FightNotEnded
+        );
+        claim_require!(
+            betting_round,
+            bet_account,
+            betting_round.claims_open,
+            BettingError::ClaimsNotOpen
+        );
+        claim_require!(
+            betting_round,
+            bet_account,
+            betting_round.claim_deadline == 0
+                || clock.unix_timestamp <= betting_round.claim_deadline,
+            BettingError::ClaimDeadlinePassed
+        );
+        claim_require!(
+            betting_round,
+            bet_account,
+            betting_round.claims_cooled_down(clock.unix_timestamp)?,
+            BettingError::ClaimCooldownActive
+        );
+        claim_require!(
+            betting_round,
+            bet_account,
+            betting_round.settled(clock.unix_timestamp)?,
+            BettingError::SettlementPending
+        );
+        claim_require!(
+            betting_round,
+            bet_account,
+            !bet_account.payout_claimed,
+            BettingError::PayoutAlreadyClaimed
+        );
+        claim_require!(
+            betting_round,
+            bet_account,
+            bet_account.bettor == ctx.accounts.bettor.key(),
+            BettingError::Unauthorized
+        );
+        // Unwrapping closes the bettor's token account, which only its owner may authorize
+        claim_require!(
+            betting_round,
+            bet_account,
+            !unwrap_sol || betting_round.token_mint == native_mint::ID,
+            BettingError::UnwrapRequiresNativeMint
+        );
+        claim_require!(
+            betting_round,
+            bet_account,
+            !unwrap_sol || ctx.accounts.bettor.is_signer,
+            BettingError::UnwrapRequiresBettorSignature
+        );
+        // The context already checks each mint; this keeps escrow, payee and round in lockstep
+        claim_require!(
+            betting_round,
+            bet_account,
+            ctx.accounts.escrow_token_account.mint == betting_round.token_mint
+                && ctx.accounts.bettor_token_account.mint == betting_round.token_mint
+                && ctx.accounts.token_mint.key() == betting_round.token_mint,
+            BettingError::InvalidTokenMint
+        );
+
+        // Check if bet won
+        claim_require!(
+            betting_round,
+            bet_account,
+            betting_round.is_winning(&bet_account.prediction),
+            BettingError::BetLost
+        );
+
+        // Calculate equal share. The claimer is one of the winners, so a zero count
+        // means the round's tallies are corrupt rather than that nobody won.
+        claim_require!(
+            betting_round,
+            bet_account,
+            betting_round.total_winners() > 0,
+            BettingError::WinnerCountMismatch
+        );
+        claim_require!(
+            betting_round,
+            bet_account,
+            betting_round.is_paid_rank(bet_account.side_rank),
+            BettingError::NotTopWinner
+        );
+
+        let share = betting_round.bet_share(bet_account)?;
+
+        // Don't let winners pay a tx fee to claim nothing when the pool is split too thin
+        claim_require!(
+            betting_round,
+            bet_account,
+            share > 0,
+            BettingError::PayoutIsDust
+        );
+
+        let vested = betting_round.vested_amount(share, clock.unix_timestamp)?;
+        let payout_u64 = vested
+            .checked_sub(bet_account.claimed_so_far)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        claim_require!(
+            betting_round,
+            bet_account,
+            payout_u64 > 0,
+            BettingError::NothingVested
+        );
+        // No single winner can be owed more than the whole pool, whatever else the escrow holds
+        claim_require!(
+            betting_round,
+            bet_account,
+            payout_u64 <= betting_round.prize_pool_amount,
+            BettingError::InvalidPayout
+        );
+        let first_claim = bet_account.claimed_so_far == 0;
+        let fully_claimed = vested == share;
+
+        claim_require!(
+            betting_round,
+            bet_account,
+            ctx.accounts.escrow_token_account.amount >= payout_u64,
+            BettingError::InsufficientEscrowFunds
+        );
+
+        // Transfer equal share to winner
+        transfer_from_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_mint,
+            ctx.accounts.bettor_token_account.to_account_info(),
+            betting_round,
+            ctx.bumps.escrow_token_account,
+            payout_u64,
+        )?;
+
+        bet_account.claimed_so_far = vested;
+        bet_account.payout_claimed = fully_claimed;
+        betting_round.record_obligation_paid(bet_account, payout_u64, fully_claimed)?;
+        if fully_claimed {
+            betting_round.total_claimed_count = betting_round
+                .total_claimed_count
+                .checked_add(1)
+                .ok_or(BettingError::ArithmeticOverflow)?;
+        }
+        betting_round.total_paid_out = betting_round
+            .total_paid_out
+            .checked_add(payout_u64)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        if let Some(global_stats) = ctx.accounts.global_stats.as_mut() {
+            global_stats.total_paid_out = global_stats
+                .total_paid_out
+                .checked_add(payout_u64)
+                .ok_or(BettingError::ArithmeticOverflow)?;
+        }
+
+        if let Some(user_profile) = ctx.accounts.user_profile.as_mut() {
+            if first_claim {
+                user_profile.bets_won = user_profile
+                    .bets_won
+                    .checked_add(1)
+                    .ok_or(BettingError::ArithmeticOverflow)?;
+            }
+            user_profile.total_won = user_profile
+                .total_won
+                .checked_add(payout_u64)
+                .ok_or(BettingError::ArithmeticOverflow)?;
+        }
+
+        if fully_claimed {
+            if let Some(claim_bitmap) = ctx.accounts.claim_bitmap.as_mut() {
+                claim_bitmap.record_claim(bet_account.bet_index);
+            }
+
+            if let Some(winners_board) = ctx.accounts.winners_board.as_mut() {
+                winners_board.record(WinnerEntry {
+                    bettor: bet_account.bettor,
+                    username: bet_account.username.clone(),
+                    payout_amount: share,
+                });
+            }
+        }
+
+        emit!(PayoutClaimed {
+            round_id: betting_round.round_id,
+            bettor: ctx.accounts.bettor.key(),
+            payout_amount: payout_u64,
+            token_mint: betting_round.token_mint,
+            decimals: betting_round.token_decimals,
+            prediction: bet_account.prediction.clone(),
+            prediction_code: bet_account.prediction.code(),
+        });
+
+        ctx.accounts.escrow_token_account.reload()?;
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance: ctx.accounts.escrow_token_account.amount,
+            reason: EscrowChangeReason::Payout,
+        });
+        check_escrow_invariant(betting_round, ctx.accounts.escrow_token_account.amount)?;
+
+        // Winners keep their bet account open until fully vested, and to claim a share of
+        // any unclaimed funds later
+        if fully_claimed && !betting_round.config.redistribute_unclaimed {
+            ctx.accounts
+                .bet_account
+                .close(ctx.accounts.rent_payer.to_account_info())?;
+        }
+
+        // Closing a wSOL account releases its lamports, payout included, as native SOL
+        if unwrap_sol {
+            token_interface::close_account(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: ctx.accounts.bettor_token_account.to_account_info(),
+                    destination: ctx.accounts.bettor.to_account_info(),
+                    authority: ctx.accounts.bettor.to_account_info(),
+                },
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Claim a winning off-chain bet in a merkle round by proving leaf `index` of the committed
+    /// tree is `(index, bettor, prediction)`. Pays the equal share and marks the leaf claimed.
+    pub fn claim_payout_merkle(
+        ctx: Context<ClaimPayoutMerkle>,
+        index: u64,
+        prediction: BossPrediction,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            betting_round.config.merkle_bets,
+            BettingError::NotMerkleRound
+        );
+        require!(
+            betting_round.phase == GamePhase::Ended,
+            BettingError::FightNotEnded
+        );
+        require!(betting_round.claims_open, BettingError::ClaimsNotOpen);
+        require!(
+            betting_round.claim_deadline == 0
+                || clock.unix_timestamp <= betting_round.claim_deadline,
+            BettingError::ClaimDeadlinePassed
+        );
+        require!(
+            betting_round.claims_cooled_down(clock.unix_timestamp)?,
+            BettingError::ClaimCooldownActive
+        );
+        require!(
+            betting_round.settled(clock.unix_timestamp)?,
+            BettingError::SettlementPending
+        );
+        require!(
+            index < MERKLE_CLAIMS_CAPACITY,
+            BettingError::MerkleIndexOutOfRange
+        );
+
+        let leaf = merkle_bet_leaf(index, &ctx.accounts.bettor.key(), &prediction);
+        require!(
+            verify_merkle_proof(leaf, &proof, betting_round.bets_merkle_root),
+            BettingError::InvalidMerkleProof
+        );
+        require!(betting_round.is_winning(&prediction), BettingError::BetLost);
+
+        let mut merkle_claims = ctx.accounts.merkle_claims.load_mut()?;
+        require!(
+            !merkle_claims.is_claimed(index),
+            BettingError::PayoutAlreadyClaimed
+        );
+
+        require!(
+            betting_round.total_winners() > 0,
+            BettingError::WinnerCountMismatch
+        );
+        let share = betting_round.winner_share()?;
+        require!(share > 0, BettingError::PayoutIsDust);
+        require!(
+            share <= betting_round.prize_pool_amount,
+            BettingError::InvalidPayout
+        );
+        require!(
+            ctx.accounts.escrow_token_account.amount >= share,
+            BettingError::InsufficientEscrowFunds
+        );
+
+        transfer_from_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_mint,
+            ctx.accounts.bettor_token_account.to_account_info(),
+            betting_round,
+            ctx.bumps.escrow_token_account,
+            share,
+        )?;
+
+        merkle_claims.mark_claimed(index);
+        betting_round.outstanding_obligation = betting_round
+            .outstanding_obligation
+            .checked_sub(share)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        betting_round.total_claimed_count = betting_round
+            .total_claimed_count
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        betting_round.total_paid_out = betting_round
+            .total_paid_out
+            .checked_add(share)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        emit!(PayoutClaimed {
+            round_id: betting_round.round_id,
+            bettor: ctx.accounts.bettor.key(),
+            payout_amount: share,
+            token_mint: betting_round.token_mint,
+            decimals: betting_round.token_decimals,
+            prediction_code: prediction.code(),
+            prediction,
+        });
+
+        ctx.accounts.escrow_token_account.reload()?;
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance: ctx.accounts.escrow_token_account.amount,
+            reason: EscrowChangeReason::Payout,
+        });
+        check_escrow_invariant(betting_round, ctx.accounts.escrow_token_account.amount)?;
+
+        Ok(())
+    }
+
+    /// Reassign a bet to a new owner once betting has closed; the new owner can then claim
+    /// it. The bet keeps its address and merkle leaf, both keyed by `original_bettor`.
+    pub fn transfer_bet(ctx: Context<TransferBet>, new_bettor: Pubkey) -> Result<()> {
+        let betting_round = &ctx.accounts.betting_round;
+        let bet_account = &mut ctx.accounts.bet_account;
+
+        require!(
+            betting_round.config.allow_bet_transfer,
+            BettingError::BetTransferDisabled
+        );
+        // While bets are taken, a new owner could also place a bet of their own
+        require!(
+            !matches!(betting_round.phase, GamePhase::Funding | GamePhase::Betting),
+            BettingError::BettingStillOpen
+        );
+        require!(!betting_round.closed, BettingError::RoundClosed);
+        require!(
+            !bet_account.payout_claimed,
+            BettingError::PayoutAlreadyClaimed
+        );
+        require!(
+            new_bettor != Pubkey::default() && new_bettor != bet_account.bettor,
+            BettingError::InvalidAccount
+        );
+
+        let previous_bettor = bet_account.bettor;
+        bet_account.bettor = new_bettor;
+
+        emit!(BetTransferred {
+            round_id: betting_round.round_id,
+            bet_index: bet_account.bet_index,
+            previous_bettor,
+            new_bettor,
+        });
+
+        Ok(())
+    }
+
+    /// Split the escrow left over after the claim deadline equally among winners who claimed
+    pub fn redistribute_unclaimed(ctx: Context<RedistributeUnclaimed>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.phase == GamePhase::Ended,
+            BettingError::FightNotEnded
+        );
+        require!(
+            betting_round.config.redistribute_unclaimed,
+            BettingError::RedistributionDisabled
+        );
+        require!(
+            clock.unix_timestamp > betting_round.claim_deadline,
+            BettingError::ClaimWindowStillOpen
+        );
+        require!(
+            !betting_round.unclaimed_redistributed,
+            BettingError::AlreadyRedistributed
+        );
+        require!(
+            betting_round.total_claimed_count > 0,
+            BettingError::NoWinners
+        );
+
+        let bonus_pool = betting_round.bonus_pool(ctx.accounts.escrow_token_account.amount);
+        let bonus_per_winner = bonus_pool
+            .checked_div(betting_round.total_claimed_count)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        betting_round.unclaimed_redistributed = true;
+        betting_round.bonus_per_winner = bonus_per_winner;
+
+        emit!(UnclaimedRedistributed {
+            round_id: betting_round.round_id,
+            bonus_pool,
+            claimed_winners: betting_round.total_claimed_count,
+            bonus_per_winner,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a winner's share of redistributed unclaimed funds and close the bet account
+    pub fn claim_bonus(ctx: Context<ClaimBonus>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let bet_account = &mut ctx.accounts.bet_account;
+
+        require!(
+            betting_round.unclaimed_redistributed,
+            BettingError::BonusNotAvailable
+        );
+        require!(bet_account.payout_claimed, BettingError::BonusNotAvailable);
+        require!(
+            !bet_account.bonus_claimed,
+            BettingError::BonusAlreadyClaimed
+        );
+        require!(
+            bet_account.bettor == ctx.accounts.bettor.key(),
+            BettingError::Unauthorized
+        );
+
+        let bonus_amount = betting_round.bonus_per_winner;
+        require!(
+            ctx.accounts.escrow_token_account.amount >= bonus_amount,
+            BettingError::InsufficientEscrowFunds
+        );
+
+        transfer_from_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_mint,
+            ctx.accounts.bettor_token_account.to_account_info(),
+            betting_round,
+            ctx.bumps.escrow_token_account,
+            bonus_amount,
+        )?;
+
+        bet_account.bonus_claimed = true;
+        betting_round.total_bonus_paid = betting_round
+            .total_bonus_paid
+            .checked_add(bonus_amount)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        emit!(BonusClaimed {
+            round_id: betting_round.round_id,
+            bettor: ctx.accounts.bettor.key(),
+            bonus_amount,
+        });
+
+        ctx.accounts.escrow_token_account.reload()?;
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance: ctx.accounts.escrow_token_account.amount,
+            reason: EscrowChangeReason::Bonus,
+        });
+        check_escrow_invariant(betting_round, ctx.accounts.escrow_token_account.amount)?;
+
+        ctx.accounts
+            .bet_account
+            .close(ctx.accounts.rent_payer.to_account_info())?;
+
+        Ok(())
+    }
+
+    /// Return a cancelled round's whole escrow to the treasury
+    pub fn refund_cancelled_round(ctx: Context<RefundCancelledRound>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.phase == GamePhase::Cancelled,
+            BettingError::RoundNotCancelled
+        );
+
+        // Contributors reclaim their own deposits through refund_contribution
+        let amount = ctx
+            .accounts
+            .escrow_token_account
+            .amount
+            .saturating_sub(betting_round.contributions_owed()?);
+        transfer_from_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_mint,
+            ctx.accounts.treasury_token_account.to_account_info(),
+            betting_round,
+            ctx.bumps.escrow_token_account,
+            amount,
+        )?;
+
+        betting_round.total_refunded = betting_round
+            .total_refunded
+            .checked_add(amount)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        emit!(TreasuryRefunded {
+            round_id: betting_round.round_id,
+            amount,
+        });
+
+        ctx.accounts.escrow_token_account.reload()?;
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance: ctx.accounts.escrow_token_account.amount,
+            reason: EscrowChangeReason::Refund,
+        });
+        check_escrow_invariant(betting_round, ctx.accounts.escrow_token_account.amount)?;
+
+        Ok(())
+    }
+
+    /// Return a contributor's deposit from a cancelled round and close their `Contribution`
+    pub fn refund_contribution(ctx: Context<RefundContribution>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            betting_round.phase == GamePhase::Cancelled,
+            BettingError::RoundNotCancelled
+        );
+
+        let amount = ctx.accounts.contribution.amount;
+        require!(amount > 0, BettingError::InvalidAmount);
+        require!(
+            ctx.accounts.escrow_token_account.amount >= amount,
+            BettingError::InsufficientEscrowFunds
+        );
+
+        transfer_from_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_mint,
+            ctx.accounts.contributor_token_account.to_account_info(),
+            betting_round,
+            ctx.bumps.escrow_token_account,
+            amount,
+        )?;
+
+        betting_round.contributions_refunded = betting_round
+            .contributions_refunded
+            .checked_add(amount)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        betting_round.total_refunded = betting_round
+            .total_refunded
+            .checked_add(amount)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        emit!(ContributionRefunded {
+            round_id: betting_round.round_id,
+            contributor: ctx.accounts.contributor.key(),
+            amount,
+        });
+
+        ctx.accounts.escrow_token_account.reload()?;
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance: ctx.accounts.escrow_token_account.amount,
+            reason: EscrowChangeReason::Refund,
+        });
+        check_escrow_invariant(betting_round, ctx.accounts.escrow_token_account.amount)?;
+
+        Ok(())
+    }
+
+    /// After resolution and before any claim, return to the treasury the part of a treasury
+    /// seed sized for bets that never came: `seed * (expected - actual) / expected`. Winners'
+    /// shares shrink with the pool; what they're then owed stays fully funded.
+    pub fn reclaim_excess_seed(ctx: Context<ReclaimExcessSeed>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.phase == GamePhase::Ended,
+            BettingError::FightNotEnded
+        );
+        require!(
+            betting_round.config.expected_bets > 0,
+            BettingError::SeedReclaimDisabled
+        );
+        require!(
+            !betting_round.seed_reclaimed,
+            BettingError::SeedAlreadyReclaimed
+        );
+        // Shares are fixed once anyone has been paid
+        require!(
+            betting_round.total_paid_out == 0,
+            BettingError::ClaimsStarted
+        );
+
+        let amount = betting_round.excess_seed()?;
+        require!(amount > 0, BettingError::NothingToReclaim);
+
+        let remaining_pool = betting_round
+            .prize_pool_amount
+            .checked_sub(amount)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        betting_round.payout_pool = betting_round.payout_pool.min(remaining_pool);
+        betting_round.outstanding_obligation = betting_round.total_obligation()?;
+        require!(
+            ctx.accounts
+                .escrow_token_account
+                .amount
+                .saturating_sub(amount)
+                >= betting_round.outstanding_obligation,
+            BettingError::InsufficientEscrowFunds
+        );
+
+        transfer_from_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_mint,
+            ctx.accounts.treasury_token_account.to_account_info(),
+            betting_round,
+            ctx.bumps.escrow_token_account,
+            amount,
+        )?;
+
+        betting_round.seed_reclaimed = true;
+        betting_round.total_refunded = betting_round
+            .total_refunded
+            .checked_add(amount)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        emit!(TreasuryRefunded {
+            round_id: betting_round.round_id,
+            amount,
+        });
+
+        ctx.accounts.escrow_token_account.reload()?;
+        emit!(EscrowBalanceChanged {
+            round_id: betting_round.round_id,
+            new_balance: ctx.accounts.escrow_token_account.amount,
+            reason: EscrowChangeReason::Refund,
+        });
+        check_escrow_invariant(betting_round, ctx.accounts.escrow_token_account.amount)?;
+
+        Ok(())
+    }
+
+    /// Close a bet on a cancelled round, returning its rent to whoever funded it
+    pub fn close_cancelled_bet(ctx: Context<CloseCancelledBet>) -> Result<()> {
+        require!(
+            ctx.accounts.betting_round.phase == GamePhase::Cancelled,
+            BettingError::RoundNotCancelled
+        );
+
+        ctx.accounts
+            .bet_account
+            .close(ctx.accounts.rent_payer.to_account_info())
+    }
+
+    /// Close a bet that can't be paid in an ended round, returning its rent to whoever funded
+    /// it. No tokens move. Only allowed once `amend_outcome` can no longer flip the result.
+    pub fn close_losing_bet(ctx: Context<CloseLosingBet>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+        let bet_account = &ctx.accounts.bet_account;
+        let clock = round_clock(betting_round)?;
+
+        require!(
+            betting_round.phase == GamePhase::Ended,
+            BettingError::FightNotEnded
+        );
+        require!(
+            betting_round.outcome_final(clock.unix_timestamp)?,
+            BettingError::OutcomeNotFinal
+        );
+        require!(
+            !betting_round.is_winning(&bet_account.prediction)
+                || !betting_round.is_paid_rank(bet_account.side_rank),
+            BettingError::BetNotLost
+        );
+
+        betting_round.losing_bets_closed = betting_round
+            .losing_bets_closed
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        ctx.accounts
+            .bet_account
+            .close(ctx.accounts.rent_payer.to_account_info())
+    }
+
+    /// Close an emptied escrow, returning its rent lamports to the authority. Up to
+    /// `dust_tolerance` leftover tokens are first swept to the treasury.
+    pub fn close_betting_round(ctx: Context<CloseBettingRound>) -> Result<()> {
+        let betting_round = &mut ctx.accounts.betting_round;
+
+        require!(
+            ctx.accounts.authority.key() == betting_round.authority,
+            BettingError::Unauthorized
+        );
+        require!(
+            betting_round.phase == GamePhase::Ended || betting_round.phase == GamePhase::Cancelled,
+            BettingError::FightNotEnded
+        );
+        require!(!betting_round.closed, BettingError::RoundClosed);
+        let clock = round_clock(betting_round)?;
+        betting_round.check_closable(
+            ctx.accounts.escrow_token_account.amount,
+            clock.unix_timestamp,
+        )?;
+
+        // Within-tolerance dust goes back to the treasury; an escrow can only close empty
+        let final_escrow_swept = ctx.accounts.escrow_token_account.amount;
+        if final_escrow_swept > 0 {
+            let token_mint = ctx
+                .accounts
+                .token_mint
+                .as_ref()
+                .ok_or(BettingError::InvalidAccount)?;
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or(BettingError::InvalidAccount)?;
+
+            transfer_from_escrow(
+                &ctx.accounts.token_program,
+                &ctx.accounts.escrow_token_account,
+                token_mint,
+                treasury_token_account.to_account_info(),
+                betting_round,
+                ctx.bumps.escrow_token_account,
+                final_escrow_swept,
+            )?;
+
+            emit!(EscrowBalanceChanged {
+                round_id: betting_round.round_id,
+                new_balance: 0,
+                reason: EscrowChangeReason::DustSwept,
+            });
+        }
+
+        let rent_reclaimed = ctx
+            .accounts
+            .escrow_token_account
+            .to_account_info()
+            .lamports();
+
+        close_escrow(
+            &ctx.accounts.token_program,
+            &ctx.accounts.escrow_token_account,
+            ctx.accounts.authority.to_account_info(),
+            betting_round,
+            ctx.bumps.escrow_token_account,
+        )?;
+
+        betting_round.closed = true;
+
+        if betting_round.counted_as_open {
+            let global_stats = ctx
+                .accounts
+                .global_stats
+                .as_mut()
+                .ok_or(BettingError::InvalidAccount)?;
+            global_stats.record_round_closed()?;
+        }
+
+        // Terminal lifecycle event; indexers can stop tracking the round
+        emit!(RoundClosed {
+            round_id: betting_round.round_id,
+            final_escrow_swept,
+            rent_reclaimed,
+        });
+
+        Ok(())
+    }
+}
+
+// =================================================================
+// ⭐️ HELPERS ⭐️
+// =================================================================
+
+/// Pay each (bet_account, bettor_token_account, rent_payer) triple in `remaining_accounts`
+/// its outstanding share; returns the escrow balance left afterwards. A `sweep` takes bets in
+/// ascending `bet_index` order from the round's cursor and skips any that aren't owed;
+/// `payout_claimed` keeps a bet from being paid twice.
+#[allow(clippy::too_many_arguments)]
+fn pay_winners<'info>(
+    betting_round: &mut Account<'info, BettingRound>,
+    escrow_token_account: &InterfaceAccount<'info, TokenAccount>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    mut claim_bitmap: Option<&mut Account<'info, ClaimBitmap>>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    program_id: &Pubkey,
+    sweep: bool,
+    escrow_bump: u8,
+) -> Result<u64> {
+    let mut escrow_balance = escrow_token_account.amount;
+    // The operator picks destinations in a sweep, so only canonical ATAs are accepted
+    let require_ata = sweep || betting_round.config.enforce_ata;
+
+    for triple in remaining_accounts.chunks(3) {
+        let bet_info = &triple[0];
+        let token_info = &triple[1];
+        let rent_payer_info = &triple[2];
+
+        require!(
+            bet_info.is_writable && token_info.is_writable,
+            BettingError::InvalidAccount
+        );
+        let mut bet_account: Account<'info, BetAccount> = Account::try_from(bet_info)?;
+
+        require!(
+            bet_account.belongs_to(&betting_round.key()),
+            BettingError::InvalidAccount
+        );
+
+        if sweep {
+            // Bets come in ascending bet_index order across calls; claimed and closed bets
+            // simply leave gaps
+            betting_round.advance_sweep(bet_account.bet_index)?;
+
+            if bet_account.payout_claimed
+                || !betting_round.is_winning(&bet_account.prediction)
+                || !betting_round.is_paid_rank(bet_account.side_rank)
+            {
+                continue;
+            }
+        }
+
+        let bettor_token_account: InterfaceAccount<'info, TokenAccount> =
+            InterfaceAccount::try_from(token_info)?;
+        require!(
+            !bet_account.payout_claimed,
+            BettingError::PayoutAlreadyClaimed
+        );
+        require!(
+            betting_round.is_winning(&bet_account.prediction),
+            BettingError::BetLost
+        );
+        require!(
+            betting_round.is_paid_rank(bet_account.side_rank),
+            BettingError::NotTopWinner
+        );
+        require!(
+            bettor_token_account.owner == bet_account.bettor,
+            BettingError::InvalidTokenAccount
+        );
+        require!(
+            bettor_token_account.mint == betting_round.token_mint,
+            BettingError::InvalidTokenMint
+        );
+        require!(
+            !require_ata
+                || token_info.key()
+                    == get_associated_token_address_with_program_id(
+                        &bet_account.bettor,
+                        &betting_round.token_mint,
+                        &token_program.key(),
+                    ),
+            BettingError::InvalidTokenAccount
+        );
+        require_keys_eq!(
+            rent_payer_info.key(),
+            bet_account.rent_payer,
+            BettingError::InvalidAccount
+        );
+
+        let share = betting_round.bet_share(&bet_account)?;
+        require!(share > 0, BettingError::PayoutIsDust);
+        // Whatever a vesting bet already claimed is subtracted; the rest is paid in full
+        let amount = share
+            .checked_sub(bet_account.claimed_so_far)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        require!(
+            escrow_balance >= amount,
+            BettingError::InsufficientEscrowFunds
+        );
+
+        transfer_from_escrow(
+            token_program,
+            escrow_token_account,
+            token_mint,
+            token_info.clone(),
+            betting_round,
+            escrow_bump,
+            amount,
+        )?;
+        escrow_balance -= amount;
+
+        bet_account.payout_claimed = true;
+        bet_account.claimed_so_far = share;
+        betting_round.record_obligation_paid(&bet_account, amount, true)?;
+        betting_round.total_claimed_count = betting_round
+            .total_claimed_count
+            .checked_add(1)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+        betting_round.total_paid_out = betting_round
+            .total_paid_out
+            .checked_add(amount)
+            .ok_or(BettingError::ArithmeticOverflow)?;
+
+        if let Some(claim_bitmap) = claim_bitmap.as_mut() {
+            claim_bitmap.record_claim(bet_account.bet_index);
+        }
+
+        emit!(PayoutClaimed {
+            round_id: betting_round.round_id,
+            bettor: bet_account.bettor,
+            payout_amount: amount,
+            token_mint: betting_round.token_mint,
+            decimals: betting_round.token_decimals,
+            prediction: bet_account.prediction.clone(),
+            prediction_code: bet_account.prediction.code(),
+        });
+
+        // Same rule as claim_payout: keep the bet open for a later bonus share
+        if betting_round.config.redistribute_unclaimed {
+            bet_account.exit(program_id)?;
+        } else {
+            bet_account.close(rent_payer_info.clone())?;
+        }
+    }
+
+    Ok(escrow_balance)
+}
+
+/// Testing aid behind the `invariant-checks` feature: fail any money-moving instruction that
+/// leaves the escrow holding something other than the round's accounted balance
+#[cfg(feature = "invariant-checks")]
+fn check_escrow_invariant(betting_round: &BettingRound, escrow_balance: u64) -> Result<()> {
+    let expected = betting_round.expected_escrow_balance()?;
+    if escrow_balance != expected {
+        msg!(
+            "INVARIANT VIOLATION: round {} escrow holds {} but accounting expects {}",
+            betting_round.round_id,
+            escrow_balance,
+            expected
+        );
+        return err!(BettingError::InvariantViolation);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "invariant-checks"))]
+#[inline(always)]
+fn check_escrow_invariant(_betting_round: &BettingRound, _escrow_balance: u64) -> Result<()> {
+    Ok(())
+}
+
+/// The cluster clock as a round sees it. Testing aid behind the `test-time` feature: a
+/// nonzero `mock_time` set by `set_mock_time` replaces the timestamp.
+#[cfg(feature = "test-time")]
+fn round_clock(betting_round: &BettingRound) -> Result<Clock> {
+    let mut clock = Clock::get()?;
+    if betting_round.mock_time != 0 {
+        clock.unix_timestamp = betting_round.mock_time;
+    }
+    Ok(clock)
+}
+
+#[cfg(not(feature = "test-time"))]
+#[inline(always)]
+fn round_clock(_betting_round: &BettingRound) -> Result<Clock> {
+    Ok(Clock::get()?)
+}
+
+/// Check a merkle proof using sorted-pair sha256(0x01 || left || right) interior nodes
+fn verify_merkle_proof(leaf: [u8; 32], proof: &[[u8; 32]], root: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (left, right) = if node <= *sibling {
+            (node, *sibling)
+        } else {
+            (*sibling, node)
+        };
+        hashv(&[&[1u8], &left, &right]).to_bytes()
+    });
+
+    computed == root
+}
+
+/// A bet memo is a short single-line client tag
+fn validate_memo(memo: &str) -> Result<()> {
+    require!(memo.len() <= 64, BettingError::MemoTooLong);
+    require!(
+        !memo.chars().any(char::is_control),
+        BettingError::InvalidMemo
+    );
+
+    Ok(())
+}
+
+/// Transfer `amount` out of a round's escrow, signing with the escrow PDA seeds. `escrow_bump`
+/// comes from the caller's freshly derived `ctx.bumps`, never the stored `escrow_bump`.
+fn transfer_from_escrow<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    escrow_token_account: &InterfaceAccount<'info, TokenAccount>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    betting_round: &BettingRound,
+    escrow_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let round_id_bytes = betting_round.round_id.to_le_bytes();
+    let escrow_seeds: &[&[u8]] = &[
+        b"escrow",
+        betting_round.authority.as_ref(),
+        round_id_bytes.as_ref(),
+        &[escrow_bump],
+    ];
+    let signer_seeds = &[escrow_seeds];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: escrow_token_account.to_account_info(),
+                mint: token_mint.to_account_info(),
+                to,
+                authority: escrow_token_account.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        token_mint.decimals,
+    )
+}
+
+/// Create a program-owned PDA account, tolerating lamports already sent to the address
+fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    require!(account.data_is_empty(), BettingError::AlreadyBet);
+
+    let rent_lamports = Rent::get()?.minimum_balance(space);
+    let current_lamports = account.lamports();
+
+    if current_lamports == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            rent_lamports,
+            space as u64,
+            owner,
+        )
+    } else {
+        let top_up = rent_lamports.saturating_sub(current_lamports);
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Allocate {
+                    account_to_allocate: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Assign {
+                    account_to_assign: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            owner,
+        )
+    }
+}
+
+/// Bytes a token account for `token_mint` needs, including any extensions Token-2022
+/// requires for the mint
+fn token_account_space(token_mint: &InterfaceAccount<Mint>) -> Result<usize> {
+    let mint_info = token_mint.to_account_info();
+    if *mint_info.owner != Token2022::id() {
+        return Ok(anchor_spl::token::TokenAccount::LEN);
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let required_extensions =
+        ExtensionType::get_required_init_account_extensions(&mint_state.get_extension_types()?);
+    Ok(ExtensionType::try_calculate_account_len::<
+        spl_token_2022::state::Account,
+    >(&required_extensions)?)
+}
+
+/// Whether winners could receive `token_mint`: a Token-2022 mint whose new accounts start
+/// frozen would leave every payout stuck
+fn mint_can_pay_out(token_mint: &InterfaceAccount<Mint>) -> Result<bool> {
+    let mint_info = token_mint.to_account_info();
+    if *mint_info.owner != Token2022::id() {
+        return Ok(true);
+    }
+
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    Ok(match mint_state.get_extension::<DefaultAccountState>() {
+        Ok(default_state) => default_state.state != AccountState::Frozen as u8,
+        Err(_) => true,
+    })
+}
+
+/// Close a round's escrow token account, sending its rent lamports to `destination`
+fn close_escrow<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    escrow_token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination: AccountInfo<'info>,
+    betting_round: &BettingRound,
+    escrow_bump: u8,
+) -> Result<()> {
+    let round_id_bytes = betting_round.round_id.to_le_bytes();
+    let escrow_seeds: &[&[u8]] = &[
+        b"escrow",
+        betting_round.authority.as_ref(),
+        round_id_bytes.as_ref(),
+        &[escrow_bump],
+    ];
+    let signer_seeds = &[escrow_seeds];
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: escrow_token_account.to_account_info(),
+            destination,
+            authority: escrow_token_account.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+// =================================================================
+// ⭐️ ACCOUNTS CONTEXTS ⭐️
+// =================================================================
+
+#[derive(Accounts)]
+pub struct InitializeGlobalStats<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalStats::INIT_SPACE,
+        seeds = [b"global_stats", authority.key().as_ref()],
+        bump
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGlobalStats<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_stats", authority.key().as_ref()],
+        bump = global_stats.bump
+    )]
+    pub global_stats: Account<'info, GlobalStats>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUserProfile<'info> {
+    #[account(
+        init,
+        payer = bettor,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"user_profile", bettor.key().as_ref()],
+        bump
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeWinnersBoard<'info> {
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + WinnersBoard::INIT_SPACE,
+        seeds = [b"winners_board", betting_round.key().as_ref()],
+        bump
+    )]
+    pub winners_board: Account<'info, WinnersBoard>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeClaimBitmap<'info> {
+    #[account(mut)]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ClaimBitmap::INIT_SPACE,
+        seeds = [b"claim_bitmap", betting_round.key().as_ref()],
+        bump
+    )]
+    pub claim_bitmap: Box<Account<'info, ClaimBitmap>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetBlocklist<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Blocklist::INIT_SPACE,
+        seeds = [b"blocklist", betting_round.key().as_ref()],
+        bump
+    )]
+    pub blocklist: Account<'info, Blocklist>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct InitializeBettingRound<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + BettingRound::INIT_SPACE,
+        seeds = [b"betting_round", authority.key().as_ref(), round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != token_mint.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = escrow_token_account,
+        token::token_program = token_program,
+        seeds = [b"escrow", authority.key().as_ref(), round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.key() != token_mint.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != treasury.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = token_mint.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != treasury.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != token_program.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != rent.key() @ BettingError::InvalidAccount
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    // Treasury token account (must have funds to deposit prize pool)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == token_mint.key() @ BettingError::InvalidTokenMint,
+        constraint = treasury_token_account.owner == treasury.key() @ BettingError::InvalidTokenAccount,
+        constraint = treasury_token_account.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != treasury.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = authority.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = authority.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = authority.key() != token_program.key() @ BettingError::InvalidAccount,
+        constraint = authority.key() != rent.key() @ BettingError::InvalidAccount
+    )]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Treasury account (must sign to authorize prize pool deposit). PDA treasuries
+    /// can't sign here; use `initialize_betting_round_pda_treasury` instead.
+    #[account(
+        mut,
+        constraint = treasury.is_signer @ BettingError::TreasuryMustSign,
+        constraint = treasury.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = treasury.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = treasury.key() != token_program.key() @ BettingError::InvalidAccount,
+        constraint = treasury.key() != rent.key() @ BettingError::InvalidAccount
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Lifetime stats and creation limits for this authority; required so the cooldown,
+    // open-round cap and minimum HP can't be skipped by leaving it out
+    #[account(
+        mut,
+        seeds = [b"global_stats", authority.key().as_ref()],
+        bump = global_stats.bump
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRoundSeries<'info> {
+    #[account(
+        constraint = token_mint.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != treasury.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    // Treasury token account (must hold every round's prize pool)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == token_mint.key() @ BettingError::InvalidTokenMint,
+        constraint = treasury_token_account.owner == treasury.key() @ BettingError::InvalidTokenAccount,
+        constraint = treasury_token_account.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != treasury.key() @ BettingError::InvalidAccount
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = authority.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = authority.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = authority.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Treasury account (must sign to authorize the prize pool deposits)
+    #[account(
+        constraint = treasury.is_signer @ BettingError::TreasuryMustSign,
+        constraint = treasury.key() != Pubkey::default() @ BettingError::InvalidAccount
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Lifetime stats and creation limits for this authority; required so the cooldown,
+    // open-round cap and minimum HP can't be skipped by leaving it out
+    #[account(
+        mut,
+        seeds = [b"global_stats", authority.key().as_ref()],
+        bump = global_stats.bump
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct InitializeBettingRoundPdaTreasury<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + BettingRound::INIT_SPACE,
+        seeds = [b"betting_round", authority.key().as_ref(), round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != token_mint.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = escrow_token_account,
+        token::token_program = token_program,
+        seeds = [b"escrow", authority.key().as_ref(), round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.key() != token_mint.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != treasury.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = token_mint.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != treasury.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != token_program.key() @ BettingError::InvalidAccount,
+        constraint = token_mint.key() != rent.key() @ BettingError::InvalidAccount
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    // Treasury token account (must have funds to deposit prize pool)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == token_mint.key() @ BettingError::InvalidTokenMint,
+        constraint = treasury_token_account.owner == treasury.key() @ BettingError::InvalidTokenAccount,
+        constraint = treasury_token_account.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != treasury.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = authority.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = authority.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = authority.key() != token_program.key() @ BettingError::InvalidAccount,
+        constraint = authority.key() != rent.key() @ BettingError::InvalidAccount
+    )]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Program-controlled treasury for this authority; signs via its seeds
+    #[account(
+        mut,
+        seeds = [b"treasury", authority.key().as_ref()],
+        bump
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Lifetime stats and creation limits for this authority; required so the cooldown,
+    // open-round cap and minimum HP can't be skipped by leaving it out
+    #[account(
+        mut,
+        seeds = [b"global_stats", authority.key().as_ref()],
+        bump = global_stats.bump
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CloneRound<'info> {
+    #[account(
+        constraint = source_round.key() != betting_round.key() @ BettingError::InvalidAccount
+    )]
+    pub source_round: Account<'info, BettingRound>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + BettingRound::INIT_SPACE,
+        seeds = [b"betting_round", authority.key().as_ref(), round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != token_mint.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = escrow_token_account,
+        token::token_program = token_program,
+        seeds = [b"escrow", authority.key().as_ref(), round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.key() != token_mint.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != treasury.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = source_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = token_mint.key() != Pubkey::default() @ BettingError::InvalidAccount
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    // Treasury token account (must have funds to deposit prize pool)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == token_mint.key() @ BettingError::InvalidTokenMint,
+        constraint = treasury_token_account.owner == treasury.key() @ BettingError::InvalidTokenAccount,
+        constraint = treasury_token_account.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != authority.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != treasury.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = treasury_token_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = authority.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = authority.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = authority.key() != token_program.key() @ BettingError::InvalidAccount,
+        constraint = authority.key() != rent.key() @ BettingError::InvalidAccount
+    )]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Treasury account (must sign to authorize prize pool deposit). PDA treasuries
+    /// can't sign here; use `initialize_betting_round_pda_treasury` instead.
+    #[account(
+        mut,
+        constraint = treasury.is_signer @ BettingError::TreasuryMustSign,
+        constraint = treasury.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = treasury.key() != system_program.key() @ BettingError::InvalidAccount,
+        constraint = treasury.key() != token_program.key() @ BettingError::InvalidAccount,
+        constraint = treasury.key() != rent.key() @ BettingError::InvalidAccount
+    )]
+    pub treasury: UncheckedAccount<'info>,
+
+    // Lifetime stats and creation limits for this authority; required so the cooldown,
+    // open-round cap and minimum HP can't be skipped by leaving it out
+    #[account(
+        mut,
+        seeds = [b"global_stats", authority.key().as_ref()],
+        bump = global_stats.bump
+    )]
+    pub global_stats: Box<Account<'info, GlobalStats>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ContributeToPool<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != contributor.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        init_if_needed,
+        payer = contributor,
+        space = 8 + Contribution::INIT_SPACE,
+        seeds = [b"contribution", betting_round.key().as_ref(), contributor.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.key() != contributor_token_account.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = contributor_token_account.owner == contributor.key() @ BettingError::InvalidTokenAccount,
+        constraint = contributor_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = contributor.key() != Pubkey::default() @ BettingError::InvalidAccount
+    )]
+    pub contributor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AddInstallment<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.key() != treasury_token_account.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == treasury.key() @ BettingError::InvalidTokenAccount,
+        constraint = treasury_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // PDA treasuries can't sign here, so their rounds fund the whole pool upfront
+    #[account(address = betting_round.treasury @ BettingError::Unauthorized)]
+    pub treasury: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBet<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != bet_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != system_program.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    // init_if_needed so a repeat bet reaches place_bet and fails with AlreadyBet rather
+    // than the system program's generic "already in use"; check_bet_slot also refuses to
+    // re-create a bet closed after the round ended
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + BetAccount::INIT_SPACE,
+        seeds = [
+            b"bet",
+            betting_round.authority.as_ref(),
+            betting_round.round_id.to_le_bytes().as_ref(),
+            bettor.key().as_ref()
+        ],
+        bump,
+        constraint = bet_account.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != system_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bet_account: Account<'info, BetAccount>,
+
+    #[account(
+        mut,
+        constraint = bettor.key() != system_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bettor: Signer<'info>,
+
+    // Funds the bet account's rent: the bettor itself, or a sponsor
+    #[account(
+        mut,
+        constraint = payer.key() != system_program.key() @ BettingError::InvalidAccount
+    )]
+    pub payer: Signer<'info>,
+
+    // Optional cross-round stats for the bettor
+    #[account(
+        mut,
+        seeds = [b"user_profile", bettor.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Option<Account<'info, UserProfile>>,
+
+    // Optional keeper index for the round
+    #[account(
+        mut,
+        seeds = [b"claim_bitmap", betting_round.key().as_ref()],
+        bump = claim_bitmap.bump
+    )]
+    pub claim_bitmap: Option<Box<Account<'info, ClaimBitmap>>>,
+
+    // Required when the round sets `validate_mint_on_bet`
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    // Required once the round has a non-empty blocklist
+    #[account(
+        seeds = [b"blocklist", betting_round.key().as_ref()],
+        bump = blocklist.bump
+    )]
+    pub blocklist: Option<Account<'info, Blocklist>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBetsBatch<'info> {
+    #[account(
+        mut,
+        constraint = bettor.key() != system_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bettor: Signer<'info>,
+
+    // Funds every bet account's rent: the bettor itself, or a sponsor
+    #[account(
+        mut,
+        constraint = payer.key() != system_program.key() @ BettingError::InvalidAccount
+    )]
+    pub payer: Signer<'info>,
+
+    // Optional cross-round stats for the bettor
+    #[account(
+        mut,
+        seeds = [b"user_profile", bettor.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Option<Account<'info, UserProfile>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MergeRounds<'info> {
+    #[account(
+        mut,
+        constraint = source_round.key() != target_round.key() @ BettingError::InvalidAccount
+    )]
+    pub source_round: Account<'info, BettingRound>,
+
+    #[account(mut)]
+    pub target_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", source_round.authority.as_ref(), source_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = source_escrow_token_account.owner == source_escrow_token_account.key() @ BettingError::InvalidEscrowAuthority
+    )]
+    pub source_escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", target_round.authority.as_ref(), target_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = target_escrow_token_account.mint == target_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub target_escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = target_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetBetsFrozen<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenClaims<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub authority: Signer<'info>,
+}
+
+#[cfg(feature = "test-time")]
+#[derive(Accounts)]
+pub struct SetMockTime<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetOperator<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBetting<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartFightPhase<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBossHp<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != operator.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub operator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetOperatorNote<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != operator.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub operator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendFight<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != operator.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub operator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EndFight<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != operator.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub operator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveAndDistribute<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != operator.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.owner == escrow_token_account.key() @ BettingError::InvalidEscrowAuthority
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub operator: Signer<'info>,
+
+    // Optional keeper index for the round
+    #[account(
+        mut,
+        seeds = [b"claim_bitmap", betting_round.key().as_ref()],
+        bump = claim_bitmap.bump
+    )]
+    pub claim_bitmap: Option<Box<Account<'info, ClaimBitmap>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ForceClaimRemaining<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.owner == escrow_token_account.key() @ BettingError::InvalidEscrowAuthority
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub authority: Signer<'info>,
+
+    // Optional keeper index for the round
+    #[account(
+        mut,
+        seeds = [b"claim_bitmap", betting_round.key().as_ref()],
+        bump = claim_bitmap.bump
+    )]
+    pub claim_bitmap: Option<Box<Account<'info, ClaimBitmap>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AmendOutcome<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RoundView<'info> {
+    pub betting_round: Account<'info, BettingRound>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateRoundParams<'info> {
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        constraint = treasury_token_account.mint == token_mint.key() @ BettingError::InvalidTokenMint
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Optional lifetime stats, to check the authority's cooldown and round limits too
+    pub global_stats: Option<Account<'info, GlobalStats>>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyBetInclusion<'info> {
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        constraint = bet_account.belongs_to(&betting_round.key()) @ BettingError::InvalidAccount
+    )]
+    pub bet_account: Account<'info, BetAccount>,
+}
+
+#[derive(Accounts)]
+pub struct BetView<'info> {
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        constraint = bet_account.belongs_to(&betting_round.key()) @ BettingError::InvalidAccount
+    )]
+    pub bet_account: Account<'info, BetAccount>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMerkleClaims<'info> {
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<MerkleClaims>(),
+        seeds = [b"merkle_claims", betting_round.key().as_ref()],
+        bump
+    )]
+    pub merkle_claims: AccountLoader<'info, MerkleClaims>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitMerkleBets<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPayoutMerkle<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != bettor.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [b"merkle_claims", betting_round.key().as_ref()],
+        bump = merkle_claims.load()?.bump
+    )]
+    pub merkle_claims: AccountLoader<'info, MerkleClaims>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.owner == escrow_token_account.key() @ BettingError::InvalidEscrowAuthority,
+        constraint = escrow_token_account.key() == betting_round.escrow @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != bettor_token_account.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = bettor_token_account.owner == bettor.key() @ BettingError::InvalidTokenAccount,
+        constraint = bettor_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = !betting_round.config.enforce_ata || bettor_token_account.key() == get_associated_token_address_with_program_id(
+            &bettor.key(),
+            &betting_round.token_mint,
+            &token_program.key(),
+        ) @ BettingError::InvalidTokenAccount
+    )]
+    pub bettor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = bettor.key() != Pubkey::default() @ BettingError::InvalidAccount
+    )]
+    pub bettor: SystemAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct TransferBet<'info> {
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"bet",
+            betting_round.authority.as_ref(),
+            betting_round.round_id.to_le_bytes().as_ref(),
+            bet_account.original_bettor.as_ref()
+        ],
+        bump,
+        constraint = bet_account.belongs_to(&betting_round.key()) @ BettingError::InvalidAccount,
+        constraint = bet_account.bettor == bettor.key() @ BettingError::Unauthorized
+    )]
+    pub bet_account: Account<'info, BetAccount>,
+
+    pub bettor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPayout<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != bet_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        constraint = bet_account.belongs_to(&betting_round.key()) @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bet_account: Account<'info, BetAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.owner == escrow_token_account.key() @ BettingError::InvalidEscrowAuthority,
+        constraint = escrow_token_account.key() == betting_round.escrow @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = bettor_token_account.owner == bettor.key() @ BettingError::InvalidTokenAccount,
+        constraint = bettor_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = !betting_round.config.enforce_ata || bettor_token_account.key() == get_associated_token_address_with_program_id(
+            &bettor.key(),
+            &betting_round.token_mint,
+            &token_program.key(),
+        ) @ BettingError::InvalidTokenAccount,
+        constraint = bettor_token_account.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = bettor_token_account.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = bettor_token_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bettor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = bettor.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = bettor.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bettor: SystemAccount<'info>,
+
+    /// CHECK: Whoever funded the bet account; receives its rent on close
+    #[account(mut, address = bet_account.rent_payer @ BettingError::InvalidAccount)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    // Optional lifetime stats for the round's authority
+    #[account(
+        mut,
+        seeds = [b"global_stats", betting_round.authority.as_ref()],
+        bump = global_stats.bump
+    )]
+    pub global_stats: Option<Account<'info, GlobalStats>>,
+
+    // Optional recent-winners board for this round
+    #[account(
+        mut,
+        seeds = [b"winners_board", betting_round.key().as_ref()],
+        bump = winners_board.bump
+    )]
+    pub winners_board: Option<Account<'info, WinnersBoard>>,
+
+    // Optional keeper index for the round
+    #[account(
+        mut,
+        seeds = [b"claim_bitmap", betting_round.key().as_ref()],
+        bump = claim_bitmap.bump
+    )]
+    pub claim_bitmap: Option<Box<Account<'info, ClaimBitmap>>>,
+
+    // Optional cross-round stats for the bettor
+    #[account(
+        mut,
+        seeds = [b"user_profile", bettor.key().as_ref()],
+        bump = user_profile.bump
+    )]
+    pub user_profile: Option<Account<'info, UserProfile>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RedistributeUnclaimed<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBonus<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != bet_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        constraint = bet_account.belongs_to(&betting_round.key()) @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bet_account: Account<'info, BetAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.owner == escrow_token_account.key() @ BettingError::InvalidEscrowAuthority,
+        constraint = escrow_token_account.key() != bettor_token_account.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = escrow_token_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = bettor_token_account.owner == bettor.key() @ BettingError::InvalidTokenAccount,
+        constraint = bettor_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = !betting_round.config.enforce_ata || bettor_token_account.key() == get_associated_token_address_with_program_id(
+            &bettor.key(),
+            &betting_round.token_mint,
+            &token_program.key(),
+        ) @ BettingError::InvalidTokenAccount,
+        constraint = bettor_token_account.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = bettor_token_account.key() != bettor.key() @ BettingError::InvalidAccount,
+        constraint = bettor_token_account.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bettor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = bettor.key() != Pubkey::default() @ BettingError::InvalidAccount,
+        constraint = bettor.key() != token_program.key() @ BettingError::InvalidAccount
+    )]
+    pub bettor: SystemAccount<'info>,
+
+    /// CHECK: Whoever funded the bet account; receives its rent on close
+    #[account(mut, address = bet_account.rent_payer @ BettingError::InvalidAccount)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RefundCancelledRound<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.key() != treasury_token_account.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == betting_round.treasury @ BettingError::InvalidTokenAccount,
+        constraint = treasury_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RefundContribution<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != contributor.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        close = contributor,
+        seeds = [b"contribution", betting_round.key().as_ref(), contributor.key().as_ref()],
+        bump = contribution.bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.key() != contributor_token_account.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = contributor_token_account.owner == contributor.key() @ BettingError::InvalidTokenAccount,
+        constraint = contributor_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimExcessSeed<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != treasury_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = escrow_token_account.key() != treasury_token_account.key() @ BettingError::InvalidAccount
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == betting_round.treasury @ BettingError::InvalidTokenAccount,
+        constraint = treasury_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCancelledBet<'info> {
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        constraint = bet_account.belongs_to(&betting_round.key()) @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != rent_payer.key() @ BettingError::InvalidAccount
+    )]
+    pub bet_account: Account<'info, BetAccount>,
+
+    /// CHECK: Whoever funded the bet account; receives its rent on close
+    #[account(mut, address = bet_account.rent_payer @ BettingError::InvalidAccount)]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseLosingBet<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != bet_account.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        constraint = bet_account.belongs_to(&betting_round.key()) @ BettingError::InvalidAccount,
+        constraint = bet_account.key() != rent_payer.key() @ BettingError::InvalidAccount
+    )]
+    pub bet_account: Account<'info, BetAccount>,
+
+    /// CHECK: Whoever funded the bet account; receives its rent on close
+    #[account(mut, address = bet_account.rent_payer @ BettingError::InvalidAccount)]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBettingRound<'info> {
+    #[account(
+        mut,
+        constraint = betting_round.key() != escrow_token_account.key() @ BettingError::InvalidAccount,
+        constraint = betting_round.key() != authority.key() @ BettingError::InvalidAccount
+    )]
+    pub betting_round: Account<'info, BettingRound>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", betting_round.authority.as_ref(), betting_round.round_id.to_le_bytes().as_ref()],
+        bump,
+        constraint = escrow_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Required, with treasury_token_account, when there is dust to sweep
+    #[account(
+        address = betting_round.token_mint @ BettingError::InvalidTokenMint
+    )]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == betting_round.treasury @ BettingError::InvalidTokenAccount,
+        constraint = treasury_token_account.mint == betting_round.token_mint @ BettingError::InvalidTokenMint,
+        constraint = treasury_token_account.key() != escrow_token_account.key() @ BettingError::InvalidAccount
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    // Required when the round was counted against the authority's open-round cap
+    #[account(
+        mut,
+        seeds = [b"global_stats", authority.key().as_ref()],
+        bump = global_stats.bump
+    )]
+    pub global_stats: Option<Account<'info, GlobalStats>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// =================================================================
+// ⭐️ ERROR CODES ⭐️
+// =================================================================
+
+#[error_code]
+pub enum BettingError {
+    #[msg("Not in betting phase")]
+    NotInBettingPhase,
+    #[msg("Betting period has expired")]
+    BettingPeriodExpired,
+    #[msg("Username too long")]
+    UsernameTooLong,
+    #[msg("Not in fight phase")]
+    NotInFightPhase,
+    #[msg("Betting is still active")]
+    BettingStillActive,
+    #[msg("Fight period has expired")]
+    FightPeriodExpired,
+    #[msg("Fight has not finished")]
+    FightNotFinished,
+    #[msg("Fight has not ended")]
+    FightNotEnded,
+    #[msg("Payout already claimed")]
+    PayoutAlreadyClaimed,
     #[msg("Bet lost, no payout available")]
     BetLost,
     #[msg("Unauthorized")]
@@ -630,4 +5835,1510 @@ pub enum BettingError {
     InvalidTokenAccount,
     #[msg("No winners to distribute prize pool")]
     NoWinners,
-}
\ No newline at end of file
+    #[msg("Winning bet is not reflected in the round's winner count")]
+    WinnerCountMismatch,
+    #[msg("Bet was not among the earliest correct predictions")]
+    NotTopWinner,
+    #[msg("Round creation cooldown is still active")]
+    CooldownActive,
+    #[msg("Invalid cooldown")]
+    InvalidCooldown,
+    #[msg("Invalid claim window")]
+    InvalidClaimWindow,
+    #[msg("Claim deadline has passed")]
+    ClaimDeadlinePassed,
+    #[msg("Claim window is still open")]
+    ClaimWindowStillOpen,
+    #[msg("Redistribution of unclaimed funds is disabled for this round")]
+    RedistributionDisabled,
+    #[msg("Unclaimed funds already redistributed")]
+    AlreadyRedistributed,
+    #[msg("No bonus available for this bet")]
+    BonusNotAvailable,
+    #[msg("Bonus already claimed")]
+    BonusAlreadyClaimed,
+    #[msg("New bets are frozen for this round")]
+    BetsFrozen,
+    #[msg("Payout rounds down to zero")]
+    PayoutIsDust,
+    #[msg("Escrow still holds tokens")]
+    EscrowNotEmpty,
+    #[msg("Round is already closed")]
+    RoundClosed,
+    #[msg("Sudden death needs a positive duration")]
+    InvalidSuddenDeathConfig,
+    #[msg("Sudden death extension already used")]
+    SuddenDeathAlreadyUsed,
+    #[msg("Boss HP is not low enough for sudden death")]
+    SuddenDeathIneligible,
+    #[msg("Invalid batch size")]
+    InvalidBatchSize,
+    #[msg("Bettor already has a bet in this round")]
+    AlreadyBet,
+    #[msg("HP cannot exceed the boss's initial HP")]
+    HpExceedsInitial,
+    #[msg("Round is not cancelled")]
+    RoundNotCancelled,
+    #[msg("Crowdfunded rounds need a funding window; treasury rounds must not have one")]
+    InvalidFundingConfig,
+    #[msg("Round is not crowdfunded")]
+    NotCrowdfunded,
+    #[msg("Funding window has closed")]
+    FundingClosed,
+    #[msg("Betting opens when the funding window closes")]
+    FundingStillOpen,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Escrow token account is not owned by the escrow PDA")]
+    InvalidEscrowAuthority,
+    #[msg("Winning predictions mask has unknown bits")]
+    InvalidWinningPredictions,
+    #[msg("Outcome can't change once a winner has claimed")]
+    ClaimsStarted,
+    #[msg("Vesting duration must be non-negative and fit within the claim window")]
+    InvalidVestingConfig,
+    #[msg("No newly vested payout to claim yet")]
+    NothingVested,
+    #[msg("Treasury must sign; use initialize_betting_round_pda_treasury for a PDA treasury")]
+    TreasuryMustSign,
+    #[msg("Late-bet grace must be non-negative and penalty at most 10000 bps")]
+    InvalidLateBetConfig,
+    #[msg("Authority already has the maximum number of open rounds")]
+    TooManyOpenRounds,
+    #[msg("Rounds with vesting payouts must be claimed by each winner")]
+    DistributionUnsupported,
+    #[msg("Initial boss HP must be non-zero and at least the authority's minimum")]
+    InvalidInitialHp,
+    #[msg("Betting start delay must be non-negative")]
+    InvalidStartDelay,
+    #[msg("Betting has not opened yet")]
+    BettingNotStarted,
+    #[msg("Betting duration must be at least MIN_BETTING_DURATION seconds")]
+    InvalidDuration,
+    #[msg("Memo too long")]
+    MemoTooLong,
+    #[msg("Memo contains control characters")]
+    InvalidMemo,
+    #[msg("HP percentage must be at most 10000 bps")]
+    InvalidHp,
+    #[msg("Escrow balance does not match the round's accounting")]
+    InvariantViolation,
+    #[msg("Sweep bets must be in ascending bet_index order from the cursor")]
+    SweepOutOfOrder,
+    #[msg("Every bet in this round has already been swept")]
+    SweepComplete,
+    #[msg("Treasury balance is below the prize pool")]
+    InsufficientTreasuryFunds,
+    #[msg("Only wrapped SOL payouts can be unwrapped")]
+    UnwrapRequiresNativeMint,
+    #[msg("Unwrapping requires the bettor's signature")]
+    UnwrapRequiresBettorSignature,
+    #[msg("Claim cooldown cannot be negative")]
+    InvalidClaimCooldown,
+    #[msg("Claims are not open until the post-resolution cooldown ends")]
+    ClaimCooldownActive,
+    #[msg("Bet transfers are disabled for this round")]
+    BetTransferDisabled,
+    #[msg("Claims have not been opened for this round")]
+    ClaimsNotOpen,
+    #[msg("Claims are already open for this round")]
+    ClaimsAlreadyOpen,
+    #[msg("A round with this id already exists")]
+    RoundAlreadyExists,
+    #[msg("Round is still in its funding phase; bets open after open_betting")]
+    RoundInFunding,
+    #[msg("Round is not in the funding phase")]
+    NotInFundingPhase,
+    #[msg("Tiered rounds need 1-10 tiers totalling at most 10000 bps; other kinds none")]
+    InvalidDistributionTiers,
+    #[msg("Computed payout exceeds the prize pool")]
+    InvalidPayout,
+    #[msg("Operator note must be 64 bytes or fewer")]
+    NoteTooLong,
+    #[msg("Game name must be 32 bytes or fewer with no control characters")]
+    InvalidGameName,
+    #[msg("Round has no expected turnout to reclaim against")]
+    SeedReclaimDisabled,
+    #[msg("Excess seed was already reclaimed")]
+    SeedAlreadyReclaimed,
+    #[msg("Turnout met expectations; there is no excess seed")]
+    NothingToReclaim,
+    #[msg("Prize pool is locked once bets have been placed")]
+    PoolLocked,
+    #[msg("Bettor is blocked from this round")]
+    BettorBlocked,
+    #[msg("Round has a blocklist; pass it to place_bet")]
+    BlocklistRequired,
+    #[msg("Bets for this round are committed off-chain")]
+    BetsAreOffChain,
+    #[msg("Merkle rounds must be equal-split with no top-K, vesting or redistribution")]
+    InvalidMerkleConfig,
+    #[msg("Round does not use off-chain merkle bets")]
+    NotMerkleRound,
+    #[msg("Off-chain bets have not been committed")]
+    MerkleBetsNotCommitted,
+    #[msg("Merkle root does not match the committed bets")]
+    MerkleRootMismatch,
+    #[msg("Invalid merkle proof")]
+    InvalidMerkleProof,
+    #[msg("Merkle leaf index out of range")]
+    MerkleIndexOutOfRange,
+    #[msg("Prize pool has not reached its target")]
+    PoolUnderfunded,
+    #[msg("Round does not take installments")]
+    InstallmentsDisabled,
+    #[msg("Bet can still be paid")]
+    BetNotLost,
+    #[msg("Dust tolerance exceeds the maximum")]
+    InvalidDustTolerance,
+    #[msg("Round holds crowdfunded contributions")]
+    ContributionsHeld,
+    #[msg("Round's claim bitmap must be provided")]
+    ClaimBitmapRequired,
+    #[msg("Outcome can still be amended")]
+    OutcomeNotFinal,
+    #[msg("Settlement delay must be non-negative and shorter than the claim window")]
+    InvalidSettlementDelay,
+    #[msg("Round is still settling; claims open after the settlement delay")]
+    SettlementPending,
+    #[msg("Claim bitmap must be created before the first bet")]
+    BetsAlreadyPlaced,
+    #[msg("Bets can only be transferred once betting has closed")]
+    BettingStillOpen,
+    #[msg("Escrow still holds contributions or bonuses that are owed")]
+    FundsStillOwed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bet_in(round: Pubkey) -> BetAccount {
+        let bettor = Pubkey::new_unique();
+        BetAccount {
+            bettor,
+            round_id: 1,
+            prediction: BossPrediction::Death,
+            username: String::new(),
+            timestamp: 0,
+            payout_claimed: false,
+            bonus_claimed: false,
+            side_rank: 0,
+            rent_payer: Pubkey::default(),
+            betting_end_time_snapshot: 0,
+            bet_index: 0,
+            claimed_so_far: 0,
+            is_late: false,
+            memo: String::new(),
+            round,
+            original_bettor: bettor,
+        }
+    }
+
+    fn blank_round() -> BettingRound {
+        BettingRound::deserialize(&mut &vec![0u8; BettingRound::INIT_SPACE][..]).unwrap()
+    }
+
+    #[test]
+    fn top_k_round_rejects_both_sides_winning() {
+        let mut round = blank_round();
+        let both = BossPrediction::Death.mask() | BossPrediction::Survival.mask();
+
+        assert!(round.check_winning_sides(both).is_ok());
+        round.config.top_k_winners = 2;
+        assert!(round.check_winning_sides(both).is_err());
+        assert!(round
+            .check_winning_sides(BossPrediction::Death.mask())
+            .is_ok());
+    }
+
+    #[test]
+    fn top_k_caps_paid_winners_and_ranks() {
+        let mut round = blank_round();
+        round.config.top_k_winners = 2;
+        round.total_death_bets = 5;
+        round.winning_predictions = BossPrediction::Death.mask();
+        round.payout_pool = 1_000;
+
+        assert_eq!(round.paid_winners(), 2);
+        assert_eq!(round.winner_share().unwrap(), 500);
+        assert!(round.is_paid_rank(1));
+        assert!(!round.is_paid_rank(2));
+        assert_eq!(round.total_obligation().unwrap(), 1_000);
+    }
+
+    #[test]
+    fn escrow_closes_empty_or_with_dust_within_tolerance() {
+        let mut round = blank_round();
+        assert!(round.check_closable(0, 0).is_ok());
+        assert!(round.check_closable(1, 0).is_err());
+
+        round.config.dust_tolerance = 5;
+        assert!(round.check_closable(5, 0).is_ok());
+        assert!(round.check_closable(6, 0).is_err());
+    }
+
+    #[test]
+    fn dust_is_not_swept_while_winners_are_owed() {
+        let mut round = blank_round();
+        round.config.dust_tolerance = 5;
+        round.outstanding_obligation = 3;
+        assert!(round.check_closable(3, 100).is_err());
+
+        round.claim_deadline = 100;
+        assert!(round.check_closable(3, 100).is_err());
+        assert!(round.check_closable(3, 101).is_ok());
+    }
+
+    #[test]
+    fn escrow_stays_open_while_contributions_or_bonuses_are_owed() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Cancelled;
+        round.config.dust_tolerance = 5;
+        round.total_contributed = 300;
+        for balance in [0, 300, 305] {
+            assert_eq!(
+                round.check_closable(balance, 0).unwrap_err(),
+                BettingError::FundsStillOwed.into()
+            );
+        }
+        // Tolerance covers only what sits above the amount owed
+        assert_eq!(
+            round.check_closable(306, 0).unwrap_err(),
+            BettingError::EscrowNotEmpty.into()
+        );
+        round.contributions_refunded = 300;
+        assert!(round.check_closable(5, 0).is_ok());
+
+        let mut round = blank_round();
+        round.unclaimed_redistributed = true;
+        round.bonus_per_winner = 10;
+        round.total_claimed_count = 2;
+        round.total_bonus_paid = 10;
+        assert_eq!(
+            round.check_closable(10, 0).unwrap_err(),
+            BettingError::FundsStillOwed.into()
+        );
+        round.total_bonus_paid = 20;
+        assert!(round.check_closable(0, 0).is_ok());
+    }
+
+    #[test]
+    fn late_open_betting_keeps_the_full_betting_window() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Funding;
+        round.betting_start_time = 100;
+        round.betting_end_time = 400;
+        round.fight_end_time = 700;
+
+        assert!(round.open_betting_at(99).is_err());
+        round.open_betting_at(150).unwrap();
+        assert_eq!(round.phase, GamePhase::Betting);
+        assert_eq!(round.betting_start_time, 150);
+        assert_eq!(round.betting_end_time, 450);
+        assert_eq!(round.fight_end_time, 750);
+        assert!(round.open_betting_at(200).is_err());
+    }
+
+    #[test]
+    fn sweep_completes_only_once_every_winner_is_paid() {
+        let mut round = blank_round();
+        round.total_death_bets = 3;
+        round.winning_predictions = BossPrediction::Death.mask();
+        round.payout_pool = 1_000;
+        round.outstanding_obligation = round.total_obligation().unwrap();
+        let bets: Vec<BetAccount> = (0..3)
+            .map(|i| {
+                let mut bet = bet_in(Pubkey::new_unique());
+                bet.bet_index = i;
+                bet
+            })
+            .collect();
+        let share = round.bet_share(&bets[0]).unwrap();
+
+        // One batch pays the first two winners; the third is still owed
+        for bet in &bets[..2] {
+            round.advance_sweep(bet.bet_index).unwrap();
+            round.record_obligation_paid(bet, share, true).unwrap();
+        }
+        assert_eq!(round.outstanding_obligation, share);
+        round.advance_sweep(bets[2].bet_index).unwrap();
+        round.record_obligation_paid(&bets[2], share, true).unwrap();
+        assert_eq!(round.outstanding_obligation, 0);
+    }
+
+    #[test]
+    fn sweep_cursor_only_moves_forward() {
+        let mut round = blank_round();
+        round.advance_sweep(2).unwrap();
+        assert_eq!(round.sweep_cursor, 3);
+
+        for passed in [0, 2] {
+            assert_eq!(
+                round.advance_sweep(passed).unwrap_err(),
+                BettingError::SweepOutOfOrder.into()
+            );
+        }
+        // Gaps left by claimed bets are fine
+        round.advance_sweep(7).unwrap();
+        assert_eq!(round.sweep_cursor, 8);
+    }
+
+    #[test]
+    fn cancelled_round_keeps_unrefunded_contributions_back() {
+        let mut round = blank_round();
+        round.total_contributed = 300;
+        assert_eq!(round.contributions_owed().unwrap(), 300);
+
+        round.contributions_refunded = 100;
+        assert_eq!(round.contributions_owed().unwrap(), 200);
+    }
+
+    #[test]
+    fn memo_rules_are_shared_by_single_and_batch_bets() {
+        assert!(validate_memo("web-ui").is_ok());
+        assert!(validate_memo(&"x".repeat(65)).is_err());
+        assert!(validate_memo("line\nbreak").is_err());
+    }
+
+    #[test]
+    fn outcome_becomes_final_after_amend_window_or_first_payout() {
+        let mut round = blank_round();
+        round.fight_ended_at = 1_000;
+
+        assert!(!round.outcome_final(1_000 + AMEND_WINDOW).unwrap());
+        assert!(round.outcome_final(1_001 + AMEND_WINDOW).unwrap());
+
+        round.claim_deadline = 2_000;
+        assert!(round.outcome_final(2_001).unwrap());
+
+        round.claim_deadline = 0;
+        round.total_paid_out = 1;
+        assert!(round.outcome_final(1_000).unwrap());
+    }
+
+    fn wire_byte<T: AnchorSerialize>(value: &T) -> Vec<u8> {
+        value.try_to_vec().unwrap()
+    }
+
+    #[test]
+    fn game_phase_wire_bytes_are_pinned() {
+        assert_eq!(wire_byte(&GamePhase::Betting), [0]);
+        assert_eq!(wire_byte(&GamePhase::Fighting), [1]);
+        assert_eq!(wire_byte(&GamePhase::Ended), [2]);
+        assert_eq!(wire_byte(&GamePhase::Cancelled), [3]);
+        assert_eq!(wire_byte(&GamePhase::Funding), [4]);
+    }
+
+    #[test]
+    fn boss_prediction_wire_bytes_are_pinned() {
+        assert_eq!(wire_byte(&BossPrediction::Death), [0]);
+        assert_eq!(wire_byte(&BossPrediction::Survival), [1]);
+        assert_eq!(BossPrediction::Death.code(), 0);
+        assert_eq!(BossPrediction::Survival.code(), 1);
+    }
+
+    #[test]
+    fn config_enum_wire_bytes_are_pinned() {
+        assert_eq!(wire_byte(&RoundKind::EqualSplit), [0]);
+        assert_eq!(wire_byte(&RoundKind::Tiered), [1]);
+        assert_eq!(wire_byte(&PrizePoolSource::Treasury), [0]);
+        assert_eq!(wire_byte(&PrizePoolSource::Crowdfunded), [1]);
+        assert_eq!(wire_byte(&EscrowChangeReason::Funded), [0]);
+        assert_eq!(wire_byte(&EscrowChangeReason::DustSwept), [6]);
+    }
+
+    #[test]
+    fn second_bet_is_rejected_with_already_bet() {
+        let round = blank_round();
+        let mut bet = bet_in(Pubkey::new_unique());
+        let err = round.check_bet_slot(&bet).unwrap_err();
+        assert_eq!(err, BettingError::AlreadyBet.into());
+
+        bet.bettor = Pubkey::default();
+        assert!(round.check_bet_slot(&bet).is_ok());
+    }
+
+    #[test]
+    fn closed_bet_cannot_be_recreated_after_the_round_ends() {
+        let mut round = blank_round();
+        let mut bet = bet_in(Pubkey::new_unique());
+        bet.bettor = Pubkey::default();
+
+        for phase in [GamePhase::Fighting, GamePhase::Ended, GamePhase::Cancelled] {
+            round.phase = phase;
+            let err = round.check_bet_slot(&bet).unwrap_err();
+            assert_eq!(err, BettingError::NotInBettingPhase.into());
+        }
+    }
+
+    #[test]
+    fn same_round_id_under_two_authorities_does_not_collide() {
+        let round_id = 1u64.to_le_bytes();
+        let bettor = Pubkey::new_unique();
+        let pdas = |authority: Pubkey| {
+            let (round, _) = Pubkey::find_program_address(
+                &[b"betting_round", authority.as_ref(), &round_id],
+                &crate::ID,
+            );
+            let (escrow, _) = Pubkey::find_program_address(
+                &[b"escrow", authority.as_ref(), &round_id],
+                &crate::ID,
+            );
+            let (bet, _) = Pubkey::find_program_address(
+                &[b"bet", authority.as_ref(), &round_id, bettor.as_ref()],
+                &crate::ID,
+            );
+            (round, escrow, bet)
+        };
+
+        let (round_a, escrow_a, bet_a) = pdas(Pubkey::new_unique());
+        let (round_b, escrow_b, bet_b) = pdas(Pubkey::new_unique());
+        assert_ne!(round_a, round_b);
+        assert_ne!(escrow_a, escrow_b);
+        assert_ne!(bet_a, bet_b);
+    }
+
+    #[test]
+    fn bet_is_rejected_against_another_round_with_the_same_id() {
+        let round_a = Pubkey::new_unique();
+        let round_b = Pubkey::new_unique();
+        let bet = bet_in(round_a);
+
+        assert!(bet.belongs_to(&round_a));
+        assert!(!bet.belongs_to(&round_b));
+    }
+
+    #[test]
+    fn redistribution_needs_a_claim_window() {
+        for config in [
+            RoundConfig {
+                claim_window: -1,
+                ..Default::default()
+            },
+            RoundConfig {
+                redistribute_unclaimed: true,
+                ..Default::default()
+            },
+        ] {
+            let params = RoundParams {
+                betting_duration: 3_600,
+                initial_hp: 100,
+                config,
+                ..Default::default()
+            };
+            assert_eq!(
+                params.validate().unwrap_err(),
+                BettingError::InvalidClaimWindow.into()
+            );
+        }
+    }
+
+    #[test]
+    fn redistribution_splits_only_the_unpaid_budget() {
+        let mut round = blank_round();
+        round.payout_pool = 1_000;
+        round.total_paid_out = 600;
+
+        // Escrow above the budget (e.g. late-bet penalties plus excess seed) stays put
+        assert_eq!(round.bonus_pool(900), 400);
+        assert_eq!(round.bonus_pool(300), 300);
+        assert_eq!(round.bonus_pool(300) / 3, 100);
+    }
+
+    fn merkle_node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[&[1u8], &left, &right]).to_bytes()
+    }
+
+    #[test]
+    fn bet_inclusion_is_verified_against_a_small_tree() {
+        let bets: Vec<BetAccount> = (0..3)
+            .map(|i| {
+                let mut bet = bet_in(Pubkey::new_unique());
+                bet.timestamp = i;
+                bet
+            })
+            .collect();
+        let leaves: Vec<[u8; 32]> = bets.iter().map(BetAccount::merkle_leaf).collect();
+        let left = merkle_node(leaves[0], leaves[1]);
+        let root = merkle_node(left, leaves[2]);
+
+        assert!(verify_merkle_proof(
+            leaves[0],
+            &[leaves[1], leaves[2]],
+            root
+        ));
+        assert!(verify_merkle_proof(
+            leaves[1],
+            &[leaves[0], leaves[2]],
+            root
+        ));
+        assert!(verify_merkle_proof(leaves[2], &[left], root));
+
+        // A bet outside the tree, or one whose prediction was changed, is excluded
+        let outsider = bet_in(Pubkey::new_unique()).merkle_leaf();
+        assert!(!verify_merkle_proof(
+            outsider,
+            &[leaves[1], leaves[2]],
+            root
+        ));
+        let mut flipped = bets[0].clone();
+        flipped.prediction = BossPrediction::Survival;
+        assert!(!verify_merkle_proof(
+            flipped.merkle_leaf(),
+            &[leaves[1], leaves[2]],
+            root
+        ));
+    }
+
+    #[test]
+    fn sudden_death_needs_a_positive_duration() {
+        let params = RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                sudden_death_threshold: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            params.validate().unwrap_err(),
+            BettingError::InvalidSuddenDeathConfig.into()
+        );
+    }
+
+    #[test]
+    fn sudden_death_needs_a_living_boss_below_the_threshold() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Fighting;
+        round.config.sudden_death_threshold = 10;
+
+        round.current_hp = 9;
+        assert!(round.sudden_death_eligible());
+        round.current_hp = 10;
+        assert!(!round.sudden_death_eligible());
+        round.current_hp = 0;
+        assert!(!round.sudden_death_eligible());
+
+        round.config.sudden_death_threshold = 0;
+        round.current_hp = 1;
+        assert!(!round.sudden_death_eligible());
+    }
+
+    #[test]
+    fn hp_above_initial_is_rejected_and_repeats_are_no_ops() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Fighting;
+        round.fight_end_time = 200;
+        round.initial_hp = 100;
+        round.current_hp = 100;
+        assert_eq!(
+            round.apply_hp(101, 50).unwrap_err(),
+            BettingError::HpExceedsInitial.into()
+        );
+
+        round.apply_hp(100, 50).unwrap();
+        assert_eq!(round.hp_update_count, 0);
+
+        round.apply_hp(40, 50).unwrap();
+        round.apply_hp(40, 60).unwrap();
+        assert_eq!(round.current_hp, 40);
+        assert_eq!(round.hp_update_count, 1);
+    }
+
+    #[test]
+    fn round_without_hp_updates_is_voided_only_when_configured() {
+        let fighting = |void_on_no_activity| {
+            let mut round = blank_round();
+            round.phase = GamePhase::Fighting;
+            round.fight_end_time = 200;
+            round.initial_hp = 100;
+            round.current_hp = 100;
+            round.config.void_on_no_activity = void_on_no_activity;
+            round
+        };
+
+        let mut idle = fighting(true);
+        idle.resolve(100, [0; 32], 0, 200).unwrap();
+        assert_eq!(idle.phase, GamePhase::Cancelled);
+
+        let mut active = fighting(true);
+        active.apply_hp(80, 150).unwrap();
+        active.resolve(80, [0; 32], 0, 200).unwrap();
+        assert_eq!(active.phase, GamePhase::Ended);
+        assert!(active.is_winning(&BossPrediction::Survival));
+
+        let mut unconfigured = fighting(false);
+        unconfigured.resolve(100, [0; 32], 0, 200).unwrap();
+        assert_eq!(unconfigured.phase, GamePhase::Ended);
+    }
+
+    #[test]
+    fn funding_duration_goes_with_a_crowdfunded_pool() {
+        let params = |config| RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config,
+            ..Default::default()
+        };
+        for config in [
+            RoundConfig {
+                funding_duration: 60,
+                ..Default::default()
+            },
+            RoundConfig {
+                prize_pool_source: PrizePoolSource::Crowdfunded,
+                ..Default::default()
+            },
+        ] {
+            assert_eq!(
+                params(config).validate().unwrap_err(),
+                BettingError::InvalidFundingConfig.into()
+            );
+        }
+
+        let crowdfunded = RoundConfig {
+            prize_pool_source: PrizePoolSource::Crowdfunded,
+            funding_duration: 600,
+            ..Default::default()
+        };
+        assert!(params(crowdfunded).validate().is_ok());
+    }
+
+    #[test]
+    fn odds_cover_no_bets_lopsided_and_even_splits() {
+        let mut round = blank_round();
+        let odds = round.odds();
+        assert_eq!((odds.death_bps, odds.survival_bps), (5_000, 5_000));
+
+        round.total_death_bets = 7;
+        round.total_survival_bets = 3;
+        round.total_bets_count = 10;
+        let odds = round.odds();
+        assert_eq!((odds.death_bps, odds.survival_bps), (7_000, 3_000));
+
+        round.total_death_bets = 5;
+        round.total_survival_bets = 5;
+        let odds = round.odds();
+        assert_eq!((odds.death_bps, odds.survival_bps), (5_000, 5_000));
+
+        // Rounding never loses a basis point
+        round.total_death_bets = 1;
+        round.total_survival_bets = 2;
+        round.total_bets_count = 3;
+        let odds = round.odds();
+        assert_eq!(odds.death_bps + odds.survival_bps, 10_000);
+    }
+
+    #[test]
+    fn payouts_are_capped_to_the_budget() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Fighting;
+        round.fight_end_time = 200;
+        round.prize_pool_amount = 1_000;
+        round.config.payout_budget = 600;
+        round.total_death_bets = 2;
+        round.resolve(0, [0; 32], 0, 150).unwrap();
+
+        assert_eq!(round.payout_pool, 600);
+        assert_eq!(round.winner_share().unwrap(), 300);
+        assert_eq!(round.outstanding_obligation, 600);
+    }
+
+    #[test]
+    fn final_hp_above_u32_errors_instead_of_killing_the_boss() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Fighting;
+        round.fight_end_time = 200;
+        round.initial_hp = 100;
+        round.current_hp = 100;
+        let err = round
+            .resolve(u32::MAX as u64 + 1, [0; 32], 0, 200)
+            .unwrap_err();
+
+        assert_eq!(err, BettingError::ArithmeticOverflow.into());
+        assert_eq!(round.phase, GamePhase::Fighting);
+        assert!(!round.boss_defeated);
+    }
+
+    #[test]
+    fn bets_get_sequential_indices_and_bitmap_bits() {
+        let mut round = blank_round();
+        round.betting_end_time = 100;
+        let mut bitmap = ClaimBitmap {
+            round_id: 1,
+            death_bets: [0; CLAIM_BITMAP_BYTES],
+            claimed: [0; CLAIM_BITMAP_BYTES],
+            bump: 0,
+        };
+
+        for (i, prediction) in [
+            BossPrediction::Death,
+            BossPrediction::Survival,
+            BossPrediction::Death,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut bet = bet_in(Pubkey::new_unique());
+            round
+                .accept_bet(
+                    &mut bet,
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    prediction,
+                    String::new(),
+                    10,
+                )
+                .unwrap();
+            assert_eq!(bet.bet_index, i as u64);
+            bitmap.record_bet(bet.bet_index, &bet.prediction);
+        }
+        assert_eq!(bitmap.death_bets[0], 0b101);
+
+        bitmap.record_claim(2);
+        assert_eq!(bitmap.claimed[0], 0b100);
+        // Indices past the capacity are simply not tracked
+        bitmap.record_claim(CLAIM_BITMAP_CAPACITY);
+        assert_eq!(
+            bitmap.claimed.iter().map(|b| b.count_ones()).sum::<u32>(),
+            1
+        );
+    }
+
+    #[test]
+    fn vesting_must_finish_inside_the_claim_window() {
+        let params = RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                claim_window: 100,
+                vesting_duration: 101,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            params.validate().unwrap_err(),
+            BettingError::InvalidVestingConfig.into()
+        );
+    }
+
+    #[test]
+    fn vesting_pays_linearly_then_in_full() {
+        let mut round = blank_round();
+        round.fight_ended_at = 1_000;
+        assert_eq!(round.vested_amount(900, 1_000).unwrap(), 900);
+
+        round.config.vesting_duration = 300;
+        assert_eq!(round.vested_amount(900, 900).unwrap(), 0);
+        assert_eq!(round.vested_amount(900, 1_100).unwrap(), 300);
+        assert_eq!(round.vested_amount(900, 1_300).unwrap(), 900);
+        assert_eq!(round.vested_amount(900, 5_000).unwrap(), 900);
+    }
+
+    #[test]
+    fn late_bet_grace_and_penalty_are_bounded() {
+        for config in [
+            RoundConfig {
+                late_bet_grace: -1,
+                ..Default::default()
+            },
+            RoundConfig {
+                late_bet_penalty_bps: 10_001,
+                ..Default::default()
+            },
+        ] {
+            let params = RoundParams {
+                betting_duration: 3_600,
+                initial_hp: 100,
+                config,
+                ..Default::default()
+            };
+            assert_eq!(
+                params.validate().unwrap_err(),
+                BettingError::InvalidLateBetConfig.into()
+            );
+        }
+    }
+
+    #[test]
+    fn late_bets_are_flagged_and_penalised_until_the_grace_runs_out() {
+        let mut round = blank_round();
+        round.betting_end_time = 100;
+        round.config.late_bet_grace = 50;
+        round.config.late_bet_penalty_bps = 2_000;
+        let mut place = |now| {
+            let mut bet = bet_in(Pubkey::new_unique());
+            round
+                .accept_bet(
+                    &mut bet,
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    BossPrediction::Death,
+                    String::new(),
+                    now,
+                )
+                .map(|_| bet)
+        };
+
+        let on_time = place(100).unwrap();
+        let late = place(150).unwrap();
+        assert!(!on_time.is_late);
+        assert!(late.is_late);
+        assert_eq!(
+            place(151).err().unwrap(),
+            BettingError::BettingPeriodExpired.into()
+        );
+
+        round.winning_predictions = BossPrediction::Death.mask();
+        round.payout_pool = 1_000;
+        assert_eq!(round.bet_share(&on_time).unwrap(), 500);
+        assert_eq!(round.bet_share(&late).unwrap(), 400);
+    }
+
+    #[test]
+    fn failed_claim_breadcrumb_names_phase_result_and_winners() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Ended;
+        round.total_death_bets = 2;
+        round.total_survival_bets = 1;
+        round.winning_predictions = BossPrediction::Death.mask();
+        let mut bet = bet_in(Pubkey::new_unique());
+        bet.prediction = BossPrediction::Survival;
+
+        assert_eq!(
+            claim_failure_breadcrumb(&round, &bet),
+            "claim failed: phase=Ended won=false winners=2"
+        );
+    }
+
+    #[test]
+    fn obligation_decrements_as_winners_claim() {
+        let mut round = blank_round();
+        round.total_death_bets = 4;
+        round.total_survival_bets = 2;
+        round.winning_predictions = BossPrediction::Death.mask();
+        round.payout_pool = 1_000;
+        round.outstanding_obligation = round.total_obligation().unwrap();
+        assert_eq!(round.outstanding_obligation, 1_000);
+
+        let bet = bet_in(Pubkey::new_unique());
+        // A vested partial claim, then the rest
+        round.record_obligation_paid(&bet, 100, false).unwrap();
+        assert_eq!(round.outstanding_obligation, 900);
+        round.record_obligation_paid(&bet, 150, true).unwrap();
+        assert_eq!(round.outstanding_obligation, 750);
+
+        // A late winner's withheld penalty is released with its final claim
+        let mut late = bet_in(Pubkey::new_unique());
+        late.is_late = true;
+        round.config.late_bet_penalty_bps = 2_000;
+        round.record_obligation_paid(&late, 200, true).unwrap();
+        assert_eq!(round.outstanding_obligation, 500);
+    }
+
+    #[test]
+    fn zero_or_below_minimum_initial_hp_is_rejected() {
+        let params = RoundParams {
+            betting_duration: 3_600,
+            ..Default::default()
+        };
+        assert_eq!(
+            params.validate().unwrap_err(),
+            BettingError::InvalidInitialHp.into()
+        );
+
+        let mut stats = GlobalStats {
+            authority: Pubkey::new_unique(),
+            rounds_created: 0,
+            total_pool_funded: 0,
+            total_paid_out: 0,
+            round_cooldown: 0,
+            last_round_created_at: 0,
+            max_concurrent_rounds: 0,
+            open_rounds: 0,
+            min_initial_hp: 50,
+            bump: 0,
+        };
+        assert_eq!(
+            stats.check_round_creation(0, 49).unwrap_err(),
+            BettingError::InvalidInitialHp.into()
+        );
+        assert!(stats.check_round_creation(0, 50).is_ok());
+
+        stats.min_initial_hp = 0;
+        assert!(stats.check_round_creation(0, 1).is_ok());
+    }
+
+    #[test]
+    fn negative_start_delay_is_rejected() {
+        let params = RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                betting_start_delay: -1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            params.validate().unwrap_err(),
+            BettingError::InvalidStartDelay.into()
+        );
+    }
+
+    #[test]
+    fn schedule_starts_betting_after_funding_and_delay() {
+        let params = RoundParams {
+            betting_duration: 3_600,
+            fight_duration: 600,
+            initial_hp: 100,
+            config: RoundConfig {
+                prize_pool_source: PrizePoolSource::Crowdfunded,
+                funding_duration: 600,
+                betting_start_delay: 60,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let schedule = params.schedule(1_000).unwrap();
+        assert_eq!(schedule.funding_end_time, 1_600);
+        assert_eq!(schedule.betting_start_time, 1_660);
+        assert_eq!(schedule.betting_end_time, 5_260);
+        assert_eq!(schedule.fight_end_time, 5_860);
+    }
+
+    #[test]
+    fn zero_negative_or_short_betting_duration_is_rejected() {
+        let params = |betting_duration| RoundParams {
+            betting_duration,
+            initial_hp: 100,
+            ..Default::default()
+        };
+        for duration in [0, -1, MIN_BETTING_DURATION - 1] {
+            assert_eq!(
+                params(duration).validate().unwrap_err(),
+                BettingError::InvalidDuration.into()
+            );
+        }
+        assert!(params(MIN_BETTING_DURATION).validate().is_ok());
+    }
+
+    #[test]
+    fn hp_percentage_scales_the_initial_hp() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Fighting;
+        round.fight_end_time = 200;
+        round.initial_hp = 1_001;
+        round.current_hp = 1_001;
+
+        assert_eq!(round.hp_from_bps(5_000).unwrap(), 500);
+        assert_eq!(round.hp_from_bps(10_000).unwrap(), 1_001);
+        assert_eq!(round.hp_from_bps(0).unwrap(), 0);
+        assert_eq!(
+            round.hp_from_bps(10_001).unwrap_err(),
+            BettingError::InvalidHp.into()
+        );
+
+        round.apply_hp(round.hp_from_bps(0).unwrap(), 150).unwrap();
+        round
+            .resolve(round.current_hp as u64, [0; 32], 0, 150)
+            .unwrap();
+        assert!(round.boss_defeated);
+    }
+
+    #[test]
+    fn well_formed_params_validate() {
+        let params = |config| RoundParams {
+            betting_duration: 3_600,
+            fight_duration: 600,
+            initial_hp: 100,
+            prize_pool_amount: 1_000,
+            config,
+            ..Default::default()
+        };
+        assert!(params(RoundConfig::default()).validate().is_ok());
+
+        let tiered = RoundConfig {
+            round_kind: RoundKind::Tiered,
+            distribution_tiers: vec![5_000, 3_000, 2_000],
+            ..Default::default()
+        };
+        assert!(params(tiered).validate().is_ok());
+
+        let crowdfunded = RoundConfig {
+            prize_pool_source: PrizePoolSource::Crowdfunded,
+            funding_duration: 600,
+            ..Default::default()
+        };
+        assert!(params(crowdfunded).validate().is_ok());
+    }
+
+    #[test]
+    fn negative_claim_cooldown_is_rejected() {
+        let params = RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                claim_cooldown: -1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            params.validate().unwrap_err(),
+            BettingError::InvalidClaimCooldown.into()
+        );
+    }
+
+    #[test]
+    fn claims_wait_out_the_cooldown() {
+        let mut round = blank_round();
+        round.fight_ended_at = 1_000;
+        round.config.claim_cooldown = 60;
+
+        assert!(!round.claims_cooled_down(1_059).unwrap());
+        assert!(round.claims_cooled_down(1_060).unwrap());
+    }
+
+    #[test]
+    fn drained_escrow_flags_the_round_underfunded() {
+        let mut round = blank_round();
+        round.outstanding_obligation = 1_000;
+        round.check_funded(0);
+        assert!(!round.underfunded);
+
+        round.phase = GamePhase::Ended;
+        round.check_funded(1_000);
+        assert!(!round.underfunded);
+        round.check_funded(999);
+        assert!(round.underfunded);
+    }
+
+    #[test]
+    fn claims_open_at_end_only_when_auto_open_is_set() {
+        for auto_open_claims in [false, true] {
+            let mut round = blank_round();
+            round.phase = GamePhase::Fighting;
+            round.fight_end_time = 200;
+            round.initial_hp = 100;
+            round.current_hp = 100;
+            round.config.auto_open_claims = auto_open_claims;
+            round.resolve(0, [0; 32], 0, 200).unwrap();
+            assert_eq!(round.claims_open, auto_open_claims);
+        }
+    }
+
+    #[test]
+    fn hp_grows_with_bets_and_stops_once_betting_closes() {
+        let mut round = blank_round();
+        round.betting_end_time = 100;
+        round.initial_hp = 100;
+        round.current_hp = 100;
+        round.config.hp_per_bet = 5;
+        let place = |round: &mut BettingRound, now| {
+            let mut bet = bet_in(Pubkey::new_unique());
+            round.accept_bet(
+                &mut bet,
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                BossPrediction::Death,
+                String::new(),
+                now,
+            )
+        };
+
+        place(&mut round, 10).unwrap();
+        place(&mut round, 20).unwrap();
+        assert_eq!((round.initial_hp, round.current_hp), (110, 110));
+
+        round.phase = GamePhase::Fighting;
+        assert!(place(&mut round, 30).is_err());
+        assert_eq!((round.initial_hp, round.current_hp), (110, 110));
+    }
+
+    #[test]
+    fn three_tiers_pay_the_first_three_of_five_winners() {
+        let mut round = blank_round();
+        round.config.round_kind = RoundKind::Tiered;
+        round.config.distribution_tiers = vec![5_000, 3_000, 2_000];
+        round.total_death_bets = 5;
+        round.winning_predictions = BossPrediction::Death.mask();
+        round.payout_pool = 1_000;
+
+        assert_eq!(round.paid_winners(), 3);
+        assert_eq!(round.tier_share(0).unwrap(), 500);
+        assert_eq!(round.tier_share(1).unwrap(), 300);
+        assert_eq!(round.tier_share(2).unwrap(), 200);
+        assert_eq!(round.tier_share(3).unwrap(), 0);
+        assert!(round.is_paid_rank(2));
+        assert!(!round.is_paid_rank(3));
+        assert_eq!(round.total_obligation().unwrap(), 1_000);
+
+        let mut fourth = bet_in(Pubkey::new_unique());
+        fourth.side_rank = 3;
+        assert_eq!(round.bet_share(&fourth).unwrap(), 0);
+    }
+
+    #[test]
+    fn tiers_must_match_the_round_kind_and_fit_the_pool() {
+        let tiered = |distribution_tiers: Vec<u16>| RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                round_kind: RoundKind::Tiered,
+                distribution_tiers,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(tiered(vec![5_000, 3_000, 2_000]).validate().is_ok());
+
+        let mut equal_split_with_tiers = tiered(vec![10_000]);
+        equal_split_with_tiers.config.round_kind = RoundKind::EqualSplit;
+        for params in [
+            equal_split_with_tiers,
+            tiered(Vec::new()),
+            tiered(vec![6_000, 4_001]),
+            tiered(vec![100; MAX_DISTRIBUTION_TIERS + 1]),
+        ] {
+            assert_eq!(
+                params.validate().unwrap_err(),
+                BettingError::InvalidDistributionTiers.into()
+            );
+        }
+    }
+
+    #[test]
+    fn bets_are_bucketed_by_time_in_the_betting_window() {
+        let mut round = blank_round();
+        round.betting_end_time = 2_400;
+        round.config.late_bet_grace = 100;
+
+        assert_eq!(round.time_bucket(0), 0);
+        assert_eq!(round.time_bucket(99), 0);
+        assert_eq!(round.time_bucket(100), 1);
+        assert_eq!(round.time_bucket(2_399), BET_TIME_BUCKETS - 1);
+        assert_eq!(round.time_bucket(2_450), BET_TIME_BUCKETS - 1);
+
+        for now in [150, 199, 2_450] {
+            let mut bet = bet_in(Pubkey::new_unique());
+            round
+                .accept_bet(
+                    &mut bet,
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    BossPrediction::Death,
+                    String::new(),
+                    now,
+                )
+                .unwrap();
+        }
+        assert_eq!(round.time_buckets[1], 2);
+        assert_eq!(round.time_buckets[BET_TIME_BUCKETS - 1], 1);
+        assert_eq!(round.time_buckets.iter().sum::<u32>(), 3);
+    }
+
+    #[test]
+    fn winner_matrix_over_outcome_prediction_and_empty_sides() {
+        for boss_dead in [true, false] {
+            for (death_bets, survival_bets) in [(0, 0), (1, 0), (0, 1), (2, 3)] {
+                let mut round = blank_round();
+                round.total_death_bets = death_bets;
+                round.total_survival_bets = survival_bets;
+                round.winning_predictions = BossPrediction::outcome(boss_dead).mask();
+                round.payout_pool = 1_200;
+
+                let winners = if boss_dead { death_bets } else { survival_bets };
+                assert_eq!(round.total_winners(), winners);
+                assert_eq!(round.is_winning(&BossPrediction::Death), boss_dead);
+                assert_eq!(round.is_winning(&BossPrediction::Survival), !boss_dead);
+
+                match 1_200u64.checked_div(winners) {
+                    None => {
+                        assert!(round.winner_share().is_err());
+                        assert_eq!(round.total_obligation().unwrap(), 0);
+                    }
+                    // A lone winner takes the whole pool and nothing is ever over-promised
+                    Some(share) => {
+                        assert_eq!(round.winner_share().unwrap(), share);
+                        assert!(round.total_obligation().unwrap() <= 1_200);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn claims_wait_out_the_settlement_delay_independently_of_the_cooldown() {
+        let mut round = blank_round();
+        round.phase = GamePhase::Ended;
+        round.claims_open = true;
+        round.fight_ended_at = 1_000;
+        round.config.claim_cooldown = 30;
+        round.config.settlement_delay = 120;
+
+        assert!(round.claims_cooled_down(1_030).unwrap());
+        assert!(!round.settled(1_119).unwrap());
+        assert!(!round.claimable_now(1_119).unwrap());
+        assert!(round.settled(1_120).unwrap());
+        assert!(round.claimable_now(1_120).unwrap());
+    }
+
+    #[test]
+    fn settlement_delay_is_non_negative_and_inside_the_claim_window() {
+        let params = |claim_window, settlement_delay| RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                claim_window,
+                settlement_delay,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(params(100, 99).validate().is_ok());
+        assert!(params(0, 1_000).validate().is_ok());
+
+        for (claim_window, settlement_delay) in [(0, -1), (100, 100), (100, 101)] {
+            assert_eq!(
+                params(claim_window, settlement_delay)
+                    .validate()
+                    .unwrap_err(),
+                BettingError::InvalidSettlementDelay.into()
+            );
+        }
+    }
+
+    #[test]
+    fn dust_tolerance_is_capped() {
+        let params = |dust_tolerance| RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                dust_tolerance,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(params(MAX_DUST_TOLERANCE).validate().is_ok());
+        assert_eq!(
+            params(MAX_DUST_TOLERANCE + 1).validate().unwrap_err(),
+            BettingError::InvalidDustTolerance.into()
+        );
+    }
+
+    #[test]
+    fn game_name_is_short_and_printable() {
+        let params = |game_name: &str| RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                game_name: game_name.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(params(&"x".repeat(32)).validate().is_ok());
+
+        for game_name in ["x".repeat(33), String::from("boss\n")] {
+            assert_eq!(
+                params(&game_name).validate().unwrap_err(),
+                BettingError::InvalidGameName.into()
+            );
+        }
+    }
+
+    #[test]
+    fn claimable_now_needs_every_gate() {
+        let mut round = blank_round();
+        round.fight_ended_at = 1_000;
+        round.config.claim_cooldown = 60;
+        round.claim_deadline = 2_000;
+        assert!(!round.claimable_now(1_500).unwrap());
+
+        round.phase = GamePhase::Ended;
+        assert!(!round.claimable_now(1_500).unwrap());
+
+        // The operator opens claims
+        round.claims_open = true;
+        assert!(!round.claimable_now(1_059).unwrap());
+        assert!(round.claimable_now(1_060).unwrap());
+        assert!(round.claimable_now(2_000).unwrap());
+        assert!(!round.claimable_now(2_001).unwrap());
+    }
+
+    #[test]
+    fn saturated_round_counters_overflow_instead_of_wrapping() {
+        let mut round = blank_round();
+        round.betting_end_time = 100;
+        round.time_buckets[0] = u32::MAX;
+        let mut bet = bet_in(Pubkey::new_unique());
+        let err = round
+            .accept_bet(
+                &mut bet,
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                BossPrediction::Death,
+                String::new(),
+                0,
+            )
+            .unwrap_err();
+        assert_eq!(err, BettingError::ArithmeticOverflow.into());
+
+        let mut round = blank_round();
+        round.phase = GamePhase::Fighting;
+        round.fight_end_time = 200;
+        round.initial_hp = 100;
+        round.current_hp = 100;
+        round.hp_update_count = u32::MAX;
+        let err = round.apply_hp(50, 150).unwrap_err();
+        assert_eq!(err, BettingError::ArithmeticOverflow.into());
+    }
+
+    #[test]
+    fn excess_seed_is_reclaimed_only_for_missing_bets() {
+        let mut round = blank_round();
+        round.prize_pool_amount = 1_000;
+        round.config.expected_bets = 10;
+
+        round.total_bets_count = 10;
+        assert_eq!(round.excess_seed().unwrap(), 0);
+        round.total_bets_count = 12;
+        assert_eq!(round.excess_seed().unwrap(), 0);
+        round.total_bets_count = 4;
+        assert_eq!(round.excess_seed().unwrap(), 600);
+        round.total_bets_count = 0;
+        assert_eq!(round.excess_seed().unwrap(), 1_000);
+    }
+
+    #[test]
+    fn pool_locks_after_the_first_bet_only_when_configured() {
+        for lock_pool_after_first_bet in [false, true] {
+            let mut round = blank_round();
+            round.betting_end_time = 100;
+            round.config.lock_pool_after_first_bet = lock_pool_after_first_bet;
+            assert!(!round.pool_locked());
+
+            let mut bet = bet_in(Pubkey::new_unique());
+            round
+                .accept_bet(
+                    &mut bet,
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    BossPrediction::Death,
+                    String::new(),
+                    10,
+                )
+                .unwrap();
+            assert_eq!(round.pool_locked(), lock_pool_after_first_bet);
+        }
+    }
+
+    #[test]
+    fn off_chain_bet_leaf_binds_index_bettor_and_prediction() {
+        let bettor = Pubkey::new_unique();
+        let leaf = merkle_bet_leaf(0, &bettor, &BossPrediction::Death);
+        let other = merkle_bet_leaf(1, &bettor, &BossPrediction::Survival);
+        let root = merkle_node(leaf, other);
+
+        assert!(verify_merkle_proof(leaf, &[other], root));
+        assert_ne!(merkle_bet_leaf(1, &bettor, &BossPrediction::Death), leaf);
+        assert_ne!(merkle_bet_leaf(0, &bettor, &BossPrediction::Survival), leaf);
+        assert!(!verify_merkle_proof(
+            merkle_bet_leaf(0, &bettor, &BossPrediction::Survival),
+            &[other],
+            root
+        ));
+    }
+
+    #[test]
+    fn merkle_claims_flag_each_leaf_once() {
+        let mut claims = MerkleClaims {
+            round_id: 1,
+            claimed: [0; MERKLE_CLAIMS_BYTES],
+            bump: 0,
+            _padding: [0; 7],
+        };
+        assert!(!claims.is_claimed(9));
+
+        claims.mark_claimed(9);
+        assert!(claims.is_claimed(9));
+        assert!(!claims.is_claimed(8));
+        assert!(!claims.is_claimed(10));
+        claims.mark_claimed(MERKLE_CLAIMS_CAPACITY - 1);
+        assert!(claims.is_claimed(MERKLE_CLAIMS_CAPACITY - 1));
+    }
+
+    #[test]
+    fn merkle_rounds_cannot_rank_winners() {
+        let params = RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            config: RoundConfig {
+                merkle_bets: true,
+                top_k_winners: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            params.validate().unwrap_err(),
+            BettingError::InvalidMerkleConfig.into()
+        );
+    }
+
+    #[test]
+    fn installment_target_must_cover_the_initial_pool() {
+        let params = |target_prize_pool| RoundParams {
+            betting_duration: 3_600,
+            initial_hp: 100,
+            prize_pool_amount: 1_000,
+            config: RoundConfig {
+                target_prize_pool,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(params(1_000).validate().is_ok());
+        assert_eq!(
+            params(999).validate().unwrap_err(),
+            BettingError::InvalidFundingConfig.into()
+        );
+    }
+
+    #[test]
+    fn fight_end_time_takes_both_a_last_hp_update_and_the_end() {
+        let fighting_round = || {
+            let mut round = blank_round();
+            round.phase = GamePhase::Fighting;
+            round.fight_end_time = 200;
+            round.initial_hp = 100;
+            round.current_hp = 100;
+            round
+        };
+        let mut round = fighting_round();
+        round.apply_hp(30, 200).unwrap();
+        assert_eq!(
+            round.apply_hp(20, 201).unwrap_err(),
+            BettingError::FightPeriodExpired.into()
+        );
+
+        let mut early = fighting_round();
+        assert_eq!(
+            early.resolve(30, [0; 32], 0, 199).unwrap_err(),
+            BettingError::FightNotFinished.into()
+        );
+        // A dead boss may end the fight early
+        early.resolve(0, [0; 32], 0, 199).unwrap();
+        assert_eq!(early.phase, GamePhase::Ended);
+
+        round.resolve(30, [0; 32], 0, 200).unwrap();
+        assert_eq!(round.phase, GamePhase::Ended);
+        assert!(round.winning_prediction == BossPrediction::Survival);
+    }
+}
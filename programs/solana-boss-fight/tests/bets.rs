@@ -0,0 +1,330 @@
+mod common;
+
+use boss_fight_betting::{
+    self as program, BatchBet, BetPlaced, BettingError, BettingOpened, BossPrediction, ClaimBitmap,
+    GamePhase, PrizePoolSource, RoundConfig, BET_TIME_BUCKETS,
+};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signer,
+};
+
+fn init_claim_bitmap_ix(env: &Env, round_id: u64) -> Instruction {
+    let round = env.round_key(round_id);
+    ix(
+        program::accounts::InitializeClaimBitmap {
+            betting_round: round,
+            claim_bitmap: round_child_pda(b"claim_bitmap", &round),
+            authority: env.authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+        },
+        program::instruction::InitializeClaimBitmap {},
+    )
+}
+
+#[tokio::test]
+async fn claim_bitmap_must_exist_before_the_first_bet() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    env.create_round(2, RoundConfig::default()).await.ok();
+
+    env.bettors(1, &[BossPrediction::Death]).await;
+    let late = init_claim_bitmap_ix(&env, 1);
+    env.send_by_authority(&[late])
+        .await
+        .expect_err(BettingError::BetsAlreadyPlaced);
+
+    let early = init_claim_bitmap_ix(&env, 2);
+    env.send_by_authority(&[early]).await.ok();
+    let bitmap = round_child_pda(b"claim_bitmap", &env.round_key(2));
+    for prediction in [BossPrediction::Survival, BossPrediction::Death] {
+        let bettor = env.bettor().await;
+        let mut accounts = env.place_bet_accounts(2, &bettor.pubkey());
+        accounts.claim_bitmap = Some(bitmap);
+        let place = ix(accounts, place_bet_args(prediction));
+        env.send(&[place], &[&bettor.key]).await.ok();
+    }
+    let bitmap: ClaimBitmap = env.state(&bitmap).await.unwrap();
+    assert_eq!(bitmap.death_bets[0], 0b10);
+}
+
+#[tokio::test]
+async fn anonymous_round_keeps_usernames_out_of_bet_events() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let anonymous = RoundConfig {
+        anonymous_bets: true,
+        ..Default::default()
+    };
+    env.create_round(2, anonymous).await.ok();
+
+    let bettor = env.bettor().await;
+    for (round_id, broadcast) in [(1, "player"), (2, "")] {
+        let placed = env
+            .bet(round_id, &bettor, BossPrediction::Death)
+            .await
+            .ok()
+            .events::<BetPlaced>();
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].username, broadcast);
+        assert_eq!(placed[0].bettor, bettor.pubkey());
+        let bet = env.bet_state(round_id, &bettor.pubkey()).await.unwrap();
+        assert_eq!(bet.username, "player");
+    }
+}
+
+fn transfer_bet_ix(
+    env: &Env,
+    round_id: u64,
+    owner: &Bettor,
+    original: &Pubkey,
+    to: &Pubkey,
+) -> Instruction {
+    ix(
+        program::accounts::TransferBet {
+            betting_round: env.round_key(round_id),
+            bet_account: env.bet_key(round_id, original),
+            bettor: owner.pubkey(),
+        },
+        program::instruction::TransferBet { new_bettor: *to },
+    )
+}
+
+#[tokio::test]
+async fn transferred_bet_is_claimed_by_its_new_owner() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        allow_bet_transfer: true,
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    let original = bettors[0].pubkey();
+    let buyer = env.bettor().await;
+
+    let early = transfer_bet_ix(&env, 1, &bettors[0], &original, &buyer.pubkey());
+    env.send(&[early], &[&bettors[0].key])
+        .await
+        .expect_err(BettingError::BettingStillOpen);
+
+    let close = env.round(1).await.betting_close_time().unwrap();
+    env.warp(close).await;
+    env.start_fight(1).await.ok();
+    let leaf = env.bet_state(1, &original).await.unwrap().merkle_leaf();
+    let transfer = transfer_bet_ix(&env, 1, &bettors[0], &original, &buyer.pubkey());
+    env.send(&[transfer], &[&bettors[0].key]).await.ok();
+    let bet = env.bet_state(1, &original).await.unwrap();
+    assert_eq!(bet.bettor, buyer.pubkey());
+    assert_eq!(bet.original_bettor, original);
+    assert_eq!(bet.merkle_leaf(), leaf);
+
+    let fight_end = env.round(1).await.fight_end_time;
+    env.warp(fight_end).await;
+    env.end_fight(1, 0).await.ok();
+    env.claim(1, &bettors[0])
+        .await
+        .expect_err(BettingError::Unauthorized);
+    let mut accounts = env.claim_accounts(1, &buyer);
+    accounts.bet_account = env.bet_key(1, &original);
+    accounts.rent_payer = original;
+    let claim = ix(
+        accounts,
+        program::instruction::ClaimPayout { unwrap_sol: false },
+    );
+    env.send(&[claim], &[]).await.ok();
+    assert_eq!(env.balance(&buyer.tokens).await, POOL);
+}
+
+#[tokio::test]
+async fn crowdfunded_round_takes_bets_only_once_betting_opens() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        prize_pool_source: PrizePoolSource::Crowdfunded,
+        funding_duration: 300,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    assert!(env.round(1).await.phase == GamePhase::Funding);
+    let bettor = env.bettor().await;
+    env.bet(1, &bettor, BossPrediction::Death)
+        .await
+        .expect_err(BettingError::RoundInFunding);
+
+    let open = ix(
+        program::accounts::OpenBetting {
+            betting_round: env.round_key(1),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::OpenBetting {},
+    );
+    env.send_by_authority(std::slice::from_ref(&open))
+        .await
+        .expect_err(BettingError::FundingStillOpen);
+    env.warp(START + 300).await;
+    let opened = env.send_by_authority(&[open]).await.ok();
+    let round = env.round(1).await;
+    assert!(round.phase == GamePhase::Betting);
+    assert_eq!(
+        opened.events::<BettingOpened>()[0].betting_end_time,
+        round.betting_end_time
+    );
+    env.bet(1, &bettor, BossPrediction::Death).await.ok();
+    assert_eq!(env.round(1).await.total_bets_count, 1);
+}
+
+#[tokio::test]
+async fn boss_hp_grows_with_each_bet_until_the_fight_starts() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        hp_per_bet: 5,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    env.bettors(1, &[BossPrediction::Death, BossPrediction::Survival])
+        .await;
+    let round = env.round(1).await;
+    assert_eq!((round.initial_hp, round.current_hp), (HP + 10, HP + 10));
+
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    let late = env.bettor().await;
+    env.bet(1, &late, BossPrediction::Death)
+        .await
+        .expect_err(BettingError::NotInBettingPhase);
+    let round = env.round(1).await;
+    assert_eq!((round.initial_hp, round.current_hp), (HP + 10, HP + 10));
+}
+
+#[tokio::test]
+async fn second_bet_from_the_same_wallet_fails_with_already_bet() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+
+    for prediction in [BossPrediction::Death, BossPrediction::Survival] {
+        env.bet(1, &bettors[0], prediction)
+            .await
+            .expect_err(BettingError::AlreadyBet);
+    }
+    let bet = env.bet_state(1, &bettors[0].pubkey()).await.unwrap();
+    assert!(bet.prediction == BossPrediction::Death);
+    assert_eq!(env.round(1).await.total_bets_count, 1);
+}
+
+#[tokio::test]
+async fn bets_fill_the_time_histogram_by_when_they_land() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        late_bet_grace: 100,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+
+    // A 3,600s window over 24 buckets puts 150s in each; late bets count in the last
+    for at in [
+        START,
+        START + 150,
+        START + 299,
+        START + BETTING,
+        START + BETTING + 100,
+    ] {
+        env.warp(at).await;
+        env.bettors(1, &[BossPrediction::Death]).await;
+    }
+    let buckets = env.round(1).await.time_buckets;
+    assert_eq!(buckets[0], 1);
+    assert_eq!(buckets[1], 2);
+    assert_eq!(buckets[BET_TIME_BUCKETS - 1], 2);
+    assert_eq!(buckets.iter().sum::<u32>(), 5);
+}
+
+#[tokio::test]
+async fn blocklisted_bettor_is_turned_away_while_others_bet() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let (blocked, allowed) = (env.bettor().await, env.bettor().await);
+    let round = env.round_key(1);
+    let blocklist = round_child_pda(b"blocklist", &round);
+    let set = ix(
+        program::accounts::SetBlocklist {
+            betting_round: round,
+            blocklist,
+            authority: env.authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+        },
+        program::instruction::SetBlocklist {
+            blocked: vec![blocked.pubkey()],
+        },
+    );
+    env.send_by_authority(&[set]).await.ok();
+
+    env.bet(1, &allowed, BossPrediction::Death)
+        .await
+        .expect_err(BettingError::BlocklistRequired);
+    let place_with_blocklist = |env: &Env, bettor: &Bettor| {
+        let mut accounts = env.place_bet_accounts(1, &bettor.pubkey());
+        accounts.blocklist = Some(blocklist);
+        ix(accounts, place_bet_args(BossPrediction::Death))
+    };
+    let place = place_with_blocklist(&env, &blocked);
+    env.send(&[place], &[&blocked.key])
+        .await
+        .expect_err(BettingError::BettorBlocked);
+    let place = place_with_blocklist(&env, &allowed);
+    env.send(&[place], &[&allowed.key]).await.ok();
+    assert!(env.bet_state(1, &blocked.pubkey()).await.is_none());
+    assert!(env.bet_state(1, &allowed.pubkey()).await.is_some());
+}
+
+#[tokio::test]
+async fn batched_bets_are_stamped_from_each_rounds_clock() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    env.create_round(2, RoundConfig::default()).await.ok();
+    let mock = ix(
+        program::accounts::SetMockTime {
+            betting_round: env.round_key(2),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::SetMockTime {
+            mock_time: START + 50,
+        },
+    );
+    env.send_by_authority(&[mock]).await.ok();
+    env.warp(START + 20).await;
+
+    let bettor = env.bettor().await;
+    let bets = [BossPrediction::Death, BossPrediction::Survival]
+        .into_iter()
+        .map(|prediction| BatchBet {
+            prediction,
+            username: String::new(),
+            memo: String::new(),
+        })
+        .collect();
+    let mut batch = ix(
+        program::accounts::PlaceBetsBatch {
+            bettor: bettor.pubkey(),
+            payer: bettor.pubkey(),
+            user_profile: None,
+            system_program: anchor_lang::system_program::ID,
+        },
+        program::instruction::PlaceBetsBatch { bets },
+    );
+    for round_id in [1, 2] {
+        batch.accounts.extend([
+            AccountMeta::new(env.round_key(round_id), false),
+            AccountMeta::new(env.bet_key(round_id, &bettor.pubkey()), false),
+        ]);
+    }
+    env.send(&[batch], &[&bettor.key]).await.ok();
+
+    for (round_id, placed_at) in [(1, START + 20), (2, START + 50)] {
+        let bet = env.bet_state(round_id, &bettor.pubkey()).await.unwrap();
+        assert_eq!(bet.timestamp, placed_at);
+    }
+}
@@ -0,0 +1,897 @@
+mod common;
+
+use boss_fight_betting::{
+    self as program, BetPlaced, BetResult, BettingError, BettingRoundInitialized, BossPrediction,
+    EscrowBalanceChanged, EscrowChangeReason, PayoutClaimed, RoundConfig, RoundKind, RoundSummary,
+    UserProfile, WinnersBoard, WINNERS_BOARD_CAPACITY,
+};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    signature::Signer,
+};
+
+#[tokio::test]
+async fn round_and_payout_events_carry_the_mint_and_decimals() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    let created = env.create_round(1, config).await.ok();
+    let initialized = created.events::<BettingRoundInitialized>();
+    assert_eq!(initialized.len(), 1);
+    assert_eq!(initialized[0].token_mint, env.mint);
+    assert_eq!(initialized[0].decimals, DECIMALS);
+
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    let claimed = env.claim(1, &bettors[0]).await.ok();
+    let payouts = claimed.events::<PayoutClaimed>();
+    assert_eq!(payouts.len(), 1);
+    assert_eq!(payouts[0].token_mint, env.mint);
+    assert_eq!(payouts[0].decimals, DECIMALS);
+    assert_eq!(payouts[0].payout_amount, POOL);
+}
+
+#[tokio::test]
+async fn unclaimed_winnings_are_split_once_among_winners_who_claimed() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        claim_window: 100,
+        redistribute_unclaimed: true,
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env
+        .bettors(
+            1,
+            &[
+                BossPrediction::Death,
+                BossPrediction::Death,
+                BossPrediction::Death,
+            ],
+        )
+        .await;
+    env.fight_to_end(1, 0).await.ok();
+    env.claim(1, &bettors[0]).await.ok();
+    env.claim(1, &bettors[1]).await.ok();
+
+    let deadline = env.round(1).await.claim_deadline;
+    env.warp(deadline + 1).await;
+    let redistribute = ix(
+        program::accounts::RedistributeUnclaimed {
+            betting_round: env.round_key(1),
+            escrow_token_account: env.escrow_key(1),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::RedistributeUnclaimed {},
+    );
+    env.send_by_authority(&[redistribute]).await.ok();
+    // The unclaimed third share is split between the two winners who claimed
+    assert_eq!(env.round(1).await.bonus_per_winner, (POOL - 2 * 333) / 2);
+
+    for bettor in &bettors[..2] {
+        let claim_bonus = ix(
+            program::accounts::ClaimBonus {
+                betting_round: env.round_key(1),
+                bet_account: env.bet_key(1, &bettor.pubkey()),
+                escrow_token_account: env.escrow_key(1),
+                token_mint: env.mint,
+                bettor_token_account: bettor.tokens,
+                bettor: bettor.pubkey(),
+                rent_payer: bettor.pubkey(),
+                token_program: env.token_program,
+            },
+            program::instruction::ClaimBonus {},
+        );
+        env.send(std::slice::from_ref(&claim_bonus), &[]).await.ok();
+        assert_eq!(env.balance(&bettor.tokens).await, 333 + 167);
+
+        // The bonus closes the bet, so a second claim has nothing to draw on
+        env.send(&[claim_bonus], &[])
+            .await
+            .expect_err(anchor_lang::error::ErrorCode::AccountNotInitialized);
+        assert_eq!(env.balance(&bettor.tokens).await, 333 + 167);
+    }
+}
+
+#[tokio::test]
+async fn claim_returns_bet_rent_to_whoever_funded_the_bet() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let self_funded = env.bettors(1, &[BossPrediction::Death]).await.remove(0);
+    let sponsored = env.bettor().await;
+    let sponsor = env.bettor().await;
+    let mut accounts = env.place_bet_accounts(1, &sponsored.pubkey());
+    accounts.payer = sponsor.pubkey();
+    let bet = ix(accounts, place_bet_args(BossPrediction::Death));
+    env.send(&[bet], &[&sponsored.key, &sponsor.key]).await.ok();
+    let bet_rent = env.lamports(&env.bet_key(1, &sponsored.pubkey())).await;
+    env.fight_to_end(1, 0).await.ok();
+
+    let before = env.lamports(&self_funded.pubkey()).await;
+    env.claim(1, &self_funded).await.ok();
+    assert_eq!(env.lamports(&self_funded.pubkey()).await, before + bet_rent);
+
+    // A sponsored bet's rent can't be redirected to the bettor
+    env.claim(1, &sponsored)
+        .await
+        .expect_err(BettingError::InvalidAccount);
+    let mut accounts = env.claim_accounts(1, &sponsored);
+    accounts.rent_payer = sponsor.pubkey();
+    let claim = ix(
+        accounts,
+        program::instruction::ClaimPayout { unwrap_sol: false },
+    );
+    let bettor_before = env.lamports(&sponsored.pubkey()).await;
+    let sponsor_before = env.lamports(&sponsor.pubkey()).await;
+    env.send(&[claim], &[]).await.ok();
+    assert_eq!(env.lamports(&sponsored.pubkey()).await, bettor_before);
+    assert_eq!(
+        env.lamports(&sponsor.pubkey()).await,
+        sponsor_before + bet_rent
+    );
+}
+
+#[tokio::test]
+async fn claims_fill_the_winners_board_up_to_its_cap() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let board = round_child_pda(b"winners_board", &env.round_key(1));
+    let init_board = ix(
+        program::accounts::InitializeWinnersBoard {
+            betting_round: env.round_key(1),
+            winners_board: board,
+            authority: env.authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+        },
+        program::instruction::InitializeWinnersBoard {},
+    );
+    env.send_by_authority(&[init_board]).await.ok();
+
+    let predictions = vec![BossPrediction::Death; WINNERS_BOARD_CAPACITY + 1];
+    let bettors = env.bettors(1, &predictions).await;
+    env.fight_to_end(1, 0).await.ok();
+    let share = POOL / bettors.len() as u64;
+
+    let mut accounts = env.claim_accounts(1, &bettors[0]);
+    accounts.winners_board = Some(board);
+    let claim = ix(
+        accounts,
+        program::instruction::ClaimPayout { unwrap_sol: false },
+    );
+    env.send(&[claim], &[]).await.ok();
+    let entries = env.state::<WinnersBoard>(&board).await.unwrap().entries;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].bettor, bettors[0].pubkey());
+    assert_eq!(entries[0].username, "player");
+    assert_eq!(entries[0].payout_amount, share);
+
+    for bettor in &bettors[1..] {
+        let mut accounts = env.claim_accounts(1, bettor);
+        accounts.winners_board = Some(board);
+        let claim = ix(
+            accounts,
+            program::instruction::ClaimPayout { unwrap_sol: false },
+        );
+        env.send(&[claim], &[]).await.ok();
+    }
+    // Full boards drop their oldest entry
+    let entries = env.state::<WinnersBoard>(&board).await.unwrap().entries;
+    assert_eq!(entries.len(), WINNERS_BOARD_CAPACITY);
+    assert_eq!(entries[0].bettor, bettors[1].pubkey());
+    assert_eq!(
+        entries.last().unwrap().bettor,
+        bettors.last().unwrap().pubkey()
+    );
+}
+
+#[tokio::test]
+async fn user_profile_aggregates_one_players_bets_across_rounds() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config.clone()).await.ok();
+    let create = env.init_round_ix(2, config);
+    env.send_by_authority(&[create]).await.ok();
+
+    let player = env.bettor().await;
+    let profile = user_profile_pda(&player.pubkey());
+    let init_profile = ix(
+        program::accounts::InitializeUserProfile {
+            user_profile: profile,
+            bettor: player.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+        },
+        program::instruction::InitializeUserProfile {},
+    );
+    env.send(&[init_profile], &[&player.key]).await.ok();
+    for (round_id, prediction) in [(1, BossPrediction::Death), (2, BossPrediction::Survival)] {
+        let mut accounts = env.place_bet_accounts(round_id, &player.pubkey());
+        accounts.user_profile = Some(profile);
+        let bet = ix(accounts, place_bet_args(prediction));
+        env.send(&[bet], &[&player.key]).await.ok();
+    }
+    env.bettors(1, &[BossPrediction::Death]).await;
+
+    // The boss dies in both rounds: the player wins the first and loses the second
+    env.fight_to_end(1, 0).await.ok();
+    env.fight_to_end(2, 0).await.ok();
+    let mut accounts = env.claim_accounts(1, &player);
+    accounts.user_profile = Some(profile);
+    let claim = ix(
+        accounts,
+        program::instruction::ClaimPayout { unwrap_sol: false },
+    );
+    env.send(&[claim], &[]).await.ok();
+
+    let stats = env.state::<UserProfile>(&profile).await.unwrap();
+    assert_eq!(stats.bettor, player.pubkey());
+    assert_eq!(stats.bets_placed, 2);
+    assert_eq!(stats.bets_won, 1);
+    assert_eq!(stats.total_won, POOL / 2);
+}
+
+#[tokio::test]
+async fn enforce_ata_rejects_payouts_to_a_non_canonical_account() {
+    let mut env = Env::new().await;
+    for (round_id, enforce_ata) in [(1, true), (2, false)] {
+        let config = RoundConfig {
+            auto_open_claims: true,
+            enforce_ata,
+            ..Default::default()
+        };
+        env.create_round(round_id, config).await.ok();
+    }
+    let strict = env.bettors(1, &[BossPrediction::Death]).await.remove(0);
+    let lax = env.bettors(2, &[BossPrediction::Death]).await.remove(0);
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    env.start_fight(2).await.ok();
+    env.warp(START + BETTING + FIGHT).await;
+    env.end_fight(1, 0).await.ok();
+    env.end_fight(2, 0).await.ok();
+
+    let side_account = env.keypair_token_account(&strict.pubkey()).await;
+    let redirected = Bettor {
+        key: strict.key.insecure_clone(),
+        tokens: side_account,
+    };
+    env.claim(1, &redirected)
+        .await
+        .expect_err(BettingError::InvalidTokenAccount);
+    env.claim(1, &strict).await.ok();
+    assert_eq!(env.balance(&strict.tokens).await, POOL);
+
+    let side_account = env.keypair_token_account(&lax.pubkey()).await;
+    let redirected = Bettor {
+        key: lax.key.insecure_clone(),
+        tokens: side_account,
+    };
+    env.claim(2, &redirected).await.ok();
+    assert_eq!(env.balance(&side_account).await, POOL);
+}
+
+async fn bet_result(env: &mut Env, round_id: u64, bettor: &Bettor) -> BetResult {
+    let view = ix(
+        program::accounts::BetView {
+            betting_round: env.round_key(round_id),
+            bet_account: env.bet_key(round_id, &bettor.pubkey()),
+        },
+        program::instruction::BetResult {},
+    );
+    env.send(&[view], &[]).await.ok().returned()
+}
+
+#[tokio::test]
+async fn bet_result_reports_winning_losing_and_unresolved_bets() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env
+        .bettors(1, &[BossPrediction::Death, BossPrediction::Survival])
+        .await;
+
+    let unresolved = BetResult {
+        won: false,
+        claimable: 0,
+        already_claimed: false,
+    };
+    assert!(bet_result(&mut env, 1, &bettors[0]).await == unresolved);
+
+    env.fight_to_end(1, 0).await.ok();
+    let winner = bet_result(&mut env, 1, &bettors[0]).await;
+    assert!(winner.won);
+    assert_eq!(winner.claimable, POOL);
+    assert!(!winner.already_claimed);
+    assert!(bet_result(&mut env, 1, &bettors[1]).await == unresolved);
+}
+
+#[tokio::test]
+async fn escrow_balance_changes_are_reported_with_their_reason() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    let created = env.create_round(1, config).await.ok();
+    let funded = created.events::<EscrowBalanceChanged>();
+    assert_eq!(funded.len(), 1);
+    assert_eq!((funded[0].round_id, funded[0].new_balance), (1, POOL));
+    assert!(funded[0].reason == EscrowChangeReason::Funded);
+
+    let bettors = env
+        .bettors(1, &[BossPrediction::Death, BossPrediction::Death])
+        .await;
+    env.fight_to_end(1, 0).await.ok();
+    for (bettor, remaining) in bettors.iter().zip([POOL / 2, 0]) {
+        let changes = env
+            .claim(1, bettor)
+            .await
+            .ok()
+            .events::<EscrowBalanceChanged>();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].new_balance, remaining);
+        assert!(changes[0].reason == EscrowChangeReason::Payout);
+        assert_eq!(env.balance(&env.escrow_key(1)).await, remaining);
+    }
+}
+
+/// `remaining_accounts` paying each of `winners`: bet, token account and rent payer
+fn payout_triples(env: &Env, round_id: u64, winners: &[Bettor]) -> Vec<AccountMeta> {
+    winners
+        .iter()
+        .flat_map(|winner| {
+            [
+                AccountMeta::new(env.bet_key(round_id, &winner.pubkey()), false),
+                AccountMeta::new(winner.tokens, false),
+                AccountMeta::new(winner.pubkey(), false),
+            ]
+        })
+        .collect()
+}
+
+fn resolve_and_distribute_ix(env: &Env, round_id: u64, winners: &[Bettor]) -> Instruction {
+    let mut distribute = ix(
+        program::accounts::ResolveAndDistribute {
+            betting_round: env.round_key(round_id),
+            escrow_token_account: env.escrow_key(round_id),
+            token_mint: env.mint,
+            operator: env.authority.pubkey(),
+            claim_bitmap: None,
+            token_program: env.token_program,
+        },
+        program::instruction::ResolveAndDistribute {
+            final_hp: 0,
+            outcome_attestation: [0; 32],
+            winning_predictions: 0,
+        },
+    );
+    distribute
+        .accounts
+        .extend(payout_triples(env, round_id, winners));
+    distribute
+}
+
+#[tokio::test]
+async fn resolve_and_distribute_pays_three_winners_in_one_call() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let mut bettors = env
+        .bettors(
+            1,
+            &[
+                BossPrediction::Death,
+                BossPrediction::Death,
+                BossPrediction::Death,
+                BossPrediction::Survival,
+            ],
+        )
+        .await;
+    let loser = bettors.pop().unwrap();
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    env.warp(START + BETTING + FIGHT).await;
+
+    let distribute = resolve_and_distribute_ix(&env, 1, &bettors);
+    env.send_by_authority(&[distribute]).await.ok();
+    for winner in &bettors {
+        assert_eq!(env.balance(&winner.tokens).await, POOL / 3);
+        assert!(env.bet_state(1, &winner.pubkey()).await.is_none());
+    }
+    assert_eq!(env.balance(&loser.tokens).await, 0);
+    assert_eq!(env.round(1).await.outstanding_obligation, 0);
+}
+
+#[tokio::test]
+async fn resolve_and_distribute_waits_for_claims_to_open() {
+    let mut env = Env::new().await;
+    let configs = [
+        RoundConfig::default(),
+        RoundConfig {
+            auto_open_claims: true,
+            claim_cooldown: 60,
+            ..Default::default()
+        },
+        RoundConfig {
+            auto_open_claims: true,
+            claim_window: 600,
+            settlement_delay: 60,
+            ..Default::default()
+        },
+    ];
+    let mut winners = Vec::new();
+    for (round_id, config) in (1..).zip(configs) {
+        env.create_round(round_id, config).await.ok();
+        winners.push(env.bettors(round_id, &[BossPrediction::Death]).await);
+    }
+    env.warp(START + BETTING).await;
+    for round_id in 1..=3 {
+        env.start_fight(round_id).await.ok();
+    }
+    env.warp(START + BETTING + FIGHT).await;
+    for (round_id, winners) in (1..).zip(&winners) {
+        let distribute = resolve_and_distribute_ix(&env, round_id, winners);
+        env.send_by_authority(&[distribute])
+            .await
+            .expect_err(BettingError::ClaimsNotOpen);
+        assert!(env
+            .bet_state(round_id, &winners[0].pubkey())
+            .await
+            .is_some());
+    }
+}
+
+#[tokio::test]
+async fn losing_claim_fails_without_touching_the_bet() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env
+        .bettors(1, &[BossPrediction::Death, BossPrediction::Survival])
+        .await;
+    env.fight_to_end(1, 0).await.ok();
+
+    // Natively-run programs print `msg!` to stdout rather than the transaction logs, so the
+    // breadcrumb's text is covered by the unit tests
+    env.claim(1, &bettors[1])
+        .await
+        .expect_err(BettingError::BetLost);
+    let bet = env.bet_state(1, &bettors[1].pubkey()).await.unwrap();
+    assert!(!bet.payout_claimed);
+    assert_eq!(env.balance(&bettors[1].tokens).await, 0);
+}
+
+fn force_claim_ix(env: &Env, round_id: u64, winners: &[Bettor]) -> Instruction {
+    let mut sweep = ix(
+        program::accounts::ForceClaimRemaining {
+            betting_round: env.round_key(round_id),
+            escrow_token_account: env.escrow_key(round_id),
+            token_mint: env.mint,
+            authority: env.authority.pubkey(),
+            claim_bitmap: None,
+            token_program: env.token_program,
+        },
+        program::instruction::ForceClaimRemaining {},
+    );
+    sweep
+        .accounts
+        .extend(payout_triples(env, round_id, winners));
+    sweep
+}
+
+#[tokio::test]
+async fn unclaimed_winnings_are_pushed_to_winners_after_the_deadline() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        claim_window: 600,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env
+        .bettors(1, &[BossPrediction::Death, BossPrediction::Death])
+        .await;
+    env.fight_to_end(1, 0).await.ok();
+    env.claim(1, &bettors[0]).await.ok();
+
+    let sweep = force_claim_ix(&env, 1, &bettors[1..]);
+    env.send_by_authority(std::slice::from_ref(&sweep))
+        .await
+        .expect_err(BettingError::ClaimWindowStillOpen);
+
+    let deadline = env.round(1).await.claim_deadline;
+    env.warp(deadline + 1).await;
+    env.send_by_authority(std::slice::from_ref(&sweep))
+        .await
+        .ok();
+    assert_eq!(env.balance(&bettors[1].tokens).await, POOL / 2);
+    assert!(env.bet_state(1, &bettors[1].pubkey()).await.is_none());
+    let round = env.round(1).await;
+    assert!(round.payouts_processed);
+    assert_eq!(round.outstanding_obligation, 0);
+
+    let again = force_claim_ix(&env, 1, &[]);
+    env.send_by_authority(&[again])
+        .await
+        .expect_err(BettingError::SweepComplete);
+}
+
+#[tokio::test]
+async fn claim_signs_with_the_derived_bump_not_the_stored_one() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+
+    let mut round = env.round(1).await;
+    round.escrow_bump = round.escrow_bump.wrapping_add(1);
+    env.overwrite(&env.round_key(1), &round).await;
+    env.claim(1, &bettors[0]).await.ok();
+    assert_eq!(env.balance(&bettors[0].tokens).await, POOL);
+}
+
+/// The `invariant-checks` comparison, made from outside the program
+async fn assert_escrow_matches_accounting(env: &mut Env, round_id: u64) {
+    let expected = env.round(round_id).await.expected_escrow_balance().unwrap();
+    assert_eq!(env.balance(&env.escrow_key(round_id)).await, expected);
+}
+
+#[tokio::test]
+async fn escrow_matches_the_accounting_through_fund_claim_and_sweep() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        claim_window: 600,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    assert_escrow_matches_accounting(&mut env, 1).await;
+
+    let bettors = env
+        .bettors(
+            1,
+            &[
+                BossPrediction::Death,
+                BossPrediction::Death,
+                BossPrediction::Death,
+                BossPrediction::Survival,
+            ],
+        )
+        .await;
+    env.fight_to_end(1, 0).await.ok();
+    env.claim(1, &bettors[0]).await.ok();
+    assert_escrow_matches_accounting(&mut env, 1).await;
+
+    let deadline = env.round(1).await.claim_deadline;
+    env.warp(deadline + 1).await;
+    let sweep = force_claim_ix(&env, 1, &bettors[1..3]);
+    env.send_by_authority(&[sweep]).await.ok();
+    assert_escrow_matches_accounting(&mut env, 1).await;
+    // Only the rounding remainder is left
+    assert_eq!(env.balance(&env.escrow_key(1)).await, POOL % 3);
+}
+
+#[tokio::test]
+async fn sweep_runs_over_several_batches_in_bet_order() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        claim_window: 600,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env
+        .bettors(
+            1,
+            &[
+                BossPrediction::Survival,
+                BossPrediction::Death,
+                BossPrediction::Death,
+                BossPrediction::Death,
+                BossPrediction::Death,
+            ],
+        )
+        .await;
+    env.fight_to_end(1, 0).await.ok();
+    let deadline = env.round(1).await.claim_deadline;
+    env.warp(deadline + 1).await;
+
+    let first = force_claim_ix(&env, 1, &bettors[1..3]);
+    env.send_by_authority(&[first]).await.ok();
+    let round = env.round(1).await;
+    assert_eq!(round.sweep_cursor, 3);
+    assert!(!round.payouts_processed);
+
+    // The losing bet at index 0 is behind the cursor now
+    let behind = force_claim_ix(&env, 1, &bettors[..1]);
+    env.send_by_authority(&[behind])
+        .await
+        .expect_err(BettingError::SweepOutOfOrder);
+    let mut reversed = force_claim_ix(&env, 1, &bettors[3..]);
+    let triples = reversed.accounts.len() - 6;
+    reversed.accounts[triples..].rotate_left(3);
+    env.send_by_authority(&[reversed])
+        .await
+        .expect_err(BettingError::SweepOutOfOrder);
+
+    let second = force_claim_ix(&env, 1, &bettors[3..]);
+    env.send_by_authority(&[second]).await.ok();
+    let round = env.round(1).await;
+    assert_eq!(round.sweep_cursor, 5);
+    assert!(round.payouts_processed);
+    for winner in &bettors[1..] {
+        assert_eq!(env.balance(&winner.tokens).await, POOL / 4);
+    }
+}
+
+#[tokio::test]
+async fn events_carry_a_stable_prediction_code() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let mut bettors = Vec::new();
+    for (prediction, code) in [(BossPrediction::Death, 0), (BossPrediction::Survival, 1)] {
+        let bettor = env.bettor().await;
+        let placed = env
+            .bet(1, &bettor, prediction)
+            .await
+            .ok()
+            .events::<BetPlaced>();
+        assert_eq!(placed[0].prediction_code, code);
+        bettors.push(bettor);
+    }
+
+    env.fight_to_end(1, 50).await.ok();
+    let paid = env
+        .claim(1, &bettors[1])
+        .await
+        .ok()
+        .events::<PayoutClaimed>();
+    assert!(paid[0].prediction == BossPrediction::Survival);
+    assert_eq!(paid[0].prediction_code, 1);
+}
+
+#[tokio::test]
+async fn claims_wait_for_the_operator_to_open_them() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    assert!(!env.round(1).await.claims_open);
+    env.claim(1, &bettors[0])
+        .await
+        .expect_err(BettingError::ClaimsNotOpen);
+
+    let open = ix(
+        program::accounts::OpenClaims {
+            betting_round: env.round_key(1),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::OpenClaims {},
+    );
+    env.send_by_authority(&[open]).await.ok();
+    env.claim(1, &bettors[0]).await.ok();
+    assert_eq!(env.balance(&bettors[0].tokens).await, POOL);
+}
+
+#[tokio::test]
+async fn stored_escrow_is_the_derived_pda_and_the_only_one_claims_accept() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config.clone()).await.ok();
+    env.create_round(2, config).await.ok();
+    for round_id in [1, 2] {
+        let round = env.round(round_id).await;
+        assert_eq!(round.escrow, escrow_pda(&env.authority.pubkey(), round_id));
+    }
+
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    let mut accounts = env.claim_accounts(1, &bettors[0]);
+    accounts.escrow_token_account = env.escrow_key(2);
+    let wrong_escrow = ix(
+        accounts,
+        program::instruction::ClaimPayout { unwrap_sol: false },
+    );
+    assert!(env.send(&[wrong_escrow], &[]).await.result.is_err());
+    env.claim(1, &bettors[0]).await.ok();
+}
+
+#[tokio::test]
+async fn three_tiers_pay_the_earliest_three_of_five_winners() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        round_kind: RoundKind::Tiered,
+        distribution_tiers: vec![5_000, 3_000, 2_000],
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env.bettors(1, &vec![BossPrediction::Death; 5]).await;
+    env.fight_to_end(1, 0).await.ok();
+
+    for (bettor, payout) in bettors.iter().zip([500, 300, 200]) {
+        env.claim(1, bettor).await.ok();
+        assert_eq!(env.balance(&bettor.tokens).await, payout);
+    }
+    for bettor in &bettors[3..] {
+        env.claim(1, bettor)
+            .await
+            .expect_err(BettingError::NotTopWinner);
+        assert_eq!(env.balance(&bettor.tokens).await, 0);
+    }
+    assert_eq!(env.balance(&env.escrow_key(1)).await, 0);
+}
+
+#[tokio::test]
+async fn stray_escrow_tokens_do_not_inflate_a_winners_share() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env
+        .bettors(1, &[BossPrediction::Death, BossPrediction::Death])
+        .await;
+    let escrow = env.escrow_key(1);
+    env.mint_to(&escrow, 5 * POOL).await;
+    env.fight_to_end(1, 0).await.ok();
+
+    for bettor in &bettors {
+        env.claim(1, bettor).await.ok();
+        assert_eq!(env.balance(&bettor.tokens).await, POOL / 2);
+    }
+    assert_eq!(env.balance(&escrow).await, 5 * POOL);
+}
+
+#[tokio::test]
+async fn lone_winners_and_losers_across_outcomes_and_empty_sides() {
+    use BossPrediction::{Death, Survival};
+
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    let cases = [
+        (true, vec![Death]),
+        (true, vec![Survival]),
+        (true, vec![Death, Survival]),
+        (false, vec![Death]),
+        (false, vec![Survival]),
+        (false, vec![Death, Survival]),
+    ];
+    let mut rounds = Vec::new();
+    for (round_id, (boss_dead, predictions)) in (1..).zip(&cases) {
+        env.create_round(round_id, config.clone()).await.ok();
+        let bettors = env.bettors(round_id, predictions).await;
+        rounds.push((round_id, *boss_dead, predictions, bettors));
+    }
+    env.warp(START + BETTING).await;
+    for (round_id, ..) in &rounds {
+        env.start_fight(*round_id).await.ok();
+    }
+    env.warp(START + BETTING + FIGHT).await;
+
+    for (round_id, boss_dead, predictions, bettors) in &rounds {
+        env.end_fight(*round_id, if *boss_dead { 0 } else { 50 })
+            .await
+            .ok();
+        let winner = BossPrediction::outcome(*boss_dead);
+        for (prediction, bettor) in predictions.iter().zip(bettors) {
+            if *prediction == winner {
+                // Each case has at most one bettor per side, so a winner takes the pool
+                env.claim(*round_id, bettor).await.ok();
+                assert_eq!(env.balance(&bettor.tokens).await, POOL);
+            } else {
+                env.claim(*round_id, bettor)
+                    .await
+                    .expect_err(BettingError::BetLost);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn claims_wait_out_the_settlement_delay() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        settlement_delay: 120,
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    let ended_at = env.round(1).await.fight_ended_at;
+
+    env.claim(1, &bettors[0])
+        .await
+        .expect_err(BettingError::SettlementPending);
+    env.warp(ended_at + 119).await;
+    env.claim(1, &bettors[0])
+        .await
+        .expect_err(BettingError::SettlementPending);
+    env.warp(ended_at + 120).await;
+    env.claim(1, &bettors[0]).await.ok();
+    assert_eq!(env.balance(&bettors[0].tokens).await, POOL);
+}
+
+async fn claimable_now(env: &mut Env, round_id: u64) -> bool {
+    let view = ix(
+        program::accounts::RoundView {
+            betting_round: env.round_key(round_id),
+        },
+        program::instruction::RoundSummary {},
+    );
+    let summary: RoundSummary = env.send(&[view], &[]).await.ok().returned();
+    summary.claimable_now
+}
+
+#[tokio::test]
+async fn round_summary_says_claimable_only_once_every_gate_is_passed() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        claim_window: 500,
+        claim_cooldown: 60,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    env.bettors(1, &[BossPrediction::Death]).await;
+    assert!(!claimable_now(&mut env, 1).await);
+
+    env.fight_to_end(1, 0).await.ok();
+    let round = env.round(1).await;
+    assert!(!claimable_now(&mut env, 1).await);
+    let open = ix(
+        program::accounts::OpenClaims {
+            betting_round: env.round_key(1),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::OpenClaims {},
+    );
+    env.send_by_authority(&[open]).await.ok();
+    assert!(!claimable_now(&mut env, 1).await);
+
+    env.warp(round.fight_ended_at + 60).await;
+    assert!(claimable_now(&mut env, 1).await);
+    env.warp(round.claim_deadline).await;
+    assert!(claimable_now(&mut env, 1).await);
+    env.warp(round.claim_deadline + 1).await;
+    assert!(!claimable_now(&mut env, 1).await);
+}
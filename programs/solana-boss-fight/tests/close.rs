@@ -0,0 +1,223 @@
+mod common;
+
+use boss_fight_betting::{
+    self as program, BettingError, BossPrediction, GamePhase, PrizePoolSource, RoundClosed,
+    RoundConfig, AMEND_WINDOW,
+};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signer};
+
+fn claimable() -> RoundConfig {
+    RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn closing_returns_the_escrow_rent_to_the_authority() {
+    let mut env = Env::new().await;
+    env.create_round(1, claimable()).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    env.claim(1, &bettors[0]).await.ok();
+
+    let escrow = env.escrow_key(1);
+    let escrow_rent = env.lamports(&escrow).await;
+    assert!(escrow_rent > 0);
+    let authority = env.authority.pubkey();
+    let before = env.lamports(&authority).await;
+    env.close_round(1).await.ok();
+
+    assert!(env.account(&escrow).await.is_none());
+    assert_eq!(env.lamports(&authority).await, before + escrow_rent);
+    assert!(env.round(1).await.closed);
+}
+
+#[tokio::test]
+async fn round_closed_reports_the_swept_dust_and_reclaimed_rent() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        dust_tolerance: 5,
+        ..claimable()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    env.claim(1, &bettors[0]).await.ok();
+    let escrow = env.escrow_key(1);
+    env.mint_to(&escrow, 3).await;
+
+    let escrow_rent = env.lamports(&escrow).await;
+    let closed = env.close_round(1).await.ok();
+    let event = &closed.events::<RoundClosed>()[0];
+    assert_eq!(event.round_id, 1);
+    assert_eq!(event.final_escrow_swept, 3);
+    assert_eq!(event.rent_reclaimed, escrow_rent);
+}
+
+#[tokio::test]
+async fn only_dust_within_the_tolerance_is_swept_on_close() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        dust_tolerance: 5,
+        ..claimable()
+    };
+    env.create_round(1, config.clone()).await.ok();
+    env.create_round(2, config).await.ok();
+    let within = env.bettors(1, &[BossPrediction::Death]).await;
+    let above = env.bettors(2, &[BossPrediction::Death]).await;
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    env.start_fight(2).await.ok();
+    env.warp(START + BETTING + FIGHT).await;
+    env.end_fight(1, 0).await.ok();
+    env.end_fight(2, 0).await.ok();
+    env.claim(1, &within[0]).await.ok();
+    env.claim(2, &above[0]).await.ok();
+    env.mint_to(&env.escrow_key(1), 5).await;
+    env.mint_to(&env.escrow_key(2), 6).await;
+
+    let treasury = env.treasury_tokens;
+    let before = env.balance(&treasury).await;
+    env.close_round(1).await.ok();
+    assert_eq!(env.balance(&treasury).await, before + 5);
+    env.close_round(2)
+        .await
+        .expect_err(BettingError::EscrowNotEmpty);
+    assert!(!env.round(2).await.closed);
+}
+
+#[tokio::test]
+async fn cancelled_round_stays_open_until_contributions_are_refunded() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        prize_pool_source: PrizePoolSource::Crowdfunded,
+        funding_duration: 300,
+        void_on_no_activity: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let contributor = env.bettor().await;
+    env.mint_to(&contributor.tokens, 200).await;
+    let contribution = Pubkey::find_program_address(
+        &[
+            b"contribution",
+            env.round_key(1).as_ref(),
+            contributor.pubkey().as_ref(),
+        ],
+        &program::ID,
+    )
+    .0;
+    let contribute = ix(
+        program::accounts::ContributeToPool {
+            betting_round: env.round_key(1),
+            contribution,
+            escrow_token_account: env.escrow_key(1),
+            token_mint: env.mint,
+            contributor_token_account: contributor.tokens,
+            contributor: contributor.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+            token_program: env.token_program,
+        },
+        program::instruction::ContributeToPool { amount: 200 },
+    );
+    env.send(&[contribute], &[&contributor.key]).await.ok();
+
+    env.warp(START + 300).await;
+    let open = ix(
+        program::accounts::OpenBetting {
+            betting_round: env.round_key(1),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::OpenBetting {},
+    );
+    env.send_by_authority(&[open]).await.ok();
+    // No HP was ever reported, so the round is voided
+    env.fight_to_end(1, 50).await.ok();
+    assert!(env.round(1).await.phase == GamePhase::Cancelled);
+    let refund = ix(
+        program::accounts::RefundCancelledRound {
+            betting_round: env.round_key(1),
+            escrow_token_account: env.escrow_key(1),
+            token_mint: env.mint,
+            treasury_token_account: env.treasury_tokens,
+            authority: env.authority.pubkey(),
+            token_program: env.token_program,
+        },
+        program::instruction::RefundCancelledRound {},
+    );
+    env.send_by_authority(&[refund]).await.ok();
+    assert_eq!(env.balance(&env.escrow_key(1)).await, 200);
+
+    env.close_round(1)
+        .await
+        .expect_err(BettingError::FundsStillOwed);
+    let reclaim = ix(
+        program::accounts::RefundContribution {
+            betting_round: env.round_key(1),
+            contribution,
+            escrow_token_account: env.escrow_key(1),
+            token_mint: env.mint,
+            contributor_token_account: contributor.tokens,
+            contributor: contributor.pubkey(),
+            token_program: env.token_program,
+        },
+        program::instruction::RefundContribution {},
+    );
+    env.send(&[reclaim], &[&contributor.key]).await.ok();
+    assert_eq!(env.balance(&contributor.tokens).await, 200);
+    env.close_round(1).await.ok();
+    assert!(env.round(1).await.closed);
+}
+
+fn close_losing_bet_ix(env: &Env, round_id: u64, bettor: &Bettor) -> Instruction {
+    ix(
+        program::accounts::CloseLosingBet {
+            betting_round: env.round_key(round_id),
+            bet_account: env.bet_key(round_id, &bettor.pubkey()),
+            rent_payer: bettor.pubkey(),
+        },
+        program::instruction::CloseLosingBet {},
+    )
+}
+
+#[tokio::test]
+async fn losing_bettor_gets_the_bet_rent_back_once_the_outcome_is_final() {
+    let mut env = Env::new().await;
+    env.create_round(1, claimable()).await.ok();
+    env.create_round(2, claimable()).await.ok();
+    let bettors = env
+        .bettors(1, &[BossPrediction::Death, BossPrediction::Survival])
+        .await;
+    let unpaid = env.bettors(2, &[BossPrediction::Survival]).await;
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    env.start_fight(2).await.ok();
+    env.warp(START + BETTING + FIGHT).await;
+    env.end_fight(1, 0).await.ok();
+    env.end_fight(2, 0).await.ok();
+
+    let (winner, loser) = (&bettors[0], &bettors[1]);
+    let early = close_losing_bet_ix(&env, 1, loser);
+    env.send(&[early], &[])
+        .await
+        .expect_err(BettingError::OutcomeNotFinal);
+
+    // The first payout locks the outcome
+    env.claim(1, winner).await.ok();
+    let bet_rent = env.lamports(&env.bet_key(1, &loser.pubkey())).await;
+    let before = env.lamports(&loser.pubkey()).await;
+    let close = close_losing_bet_ix(&env, 1, loser);
+    env.send(&[close], &[]).await.ok();
+    assert!(env.bet_state(1, &loser.pubkey()).await.is_none());
+    assert_eq!(env.lamports(&loser.pubkey()).await, before + bet_rent);
+    assert_eq!(env.round(1).await.losing_bets_closed, 1);
+
+    // Without any payout the amend window has to run out first
+    env.warp(START + BETTING + FIGHT + AMEND_WINDOW + 1).await;
+    let close = close_losing_bet_ix(&env, 2, &unpaid[0]);
+    env.send(&[close], &[]).await.ok();
+    assert!(env.bet_state(2, &unpaid[0].pubkey()).await.is_none());
+}
@@ -0,0 +1,848 @@
+//! Shared program-test harness for the instruction-level tests. Each test file starts an
+//! `Env`, which loads the program natively alongside the SPL programs, creates a funded
+//! treasury and initializes the authority's global stats.
+#![allow(dead_code)]
+
+use std::sync::Once;
+
+use anchor_lang::solana_program::{
+    instruction::Instruction as SolInstruction,
+    program::MAX_RETURN_DATA,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+};
+use anchor_lang::{
+    AccountDeserialize, AnchorDeserialize, Discriminator, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address_with_program_id, spl_associated_token_account,
+    },
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{transfer_fee, ExtensionType},
+    },
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use boss_fight_betting::{
+    self as program, BetAccount, BettingRound, BossPrediction, GlobalStats, RoundConfig,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    account_info::AccountInfo,
+    clock::Clock,
+    compute_budget::ComputeBudgetInstruction,
+    entrypoint::ProgramResult,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use solana_system_interface::instruction as system_instruction;
+
+/// Clock every test starts at
+pub const START: i64 = 1_700_000_000;
+pub const BETTING: i64 = 3_600;
+pub const FIGHT: i64 = 600;
+pub const HP: u32 = 100;
+pub const POOL: u64 = 1_000;
+pub const DECIMALS: u8 = 6;
+/// Tokens minted to the treasury at startup
+pub const TREASURY_SUPPLY: u64 = 1_000_000;
+
+/// Program-test's native stubs print `sol_log_data` to stdout; this forwards everything to
+/// them but writes event data through `sol_log`, so events land in the transaction logs
+struct EventLoggingStubs(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for EventLoggingStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.0.sol_log_compute_units()
+    }
+    fn sol_remaining_compute_units(&self) -> u64 {
+        self.0.sol_remaining_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &SolInstruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_sysvar(
+        &self,
+        sysvar_id_addr: *const u8,
+        var_addr: *mut u8,
+        offset: u64,
+        length: u64,
+    ) -> u64 {
+        self.0
+            .sol_get_sysvar(sysvar_id_addr, var_addr, offset, length)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_last_restart_slot(var_addr)
+    }
+    fn sol_get_epoch_stake(&self, vote_address: *const u8) -> u64 {
+        self.0.sol_get_epoch_stake(vote_address)
+    }
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.0.sol_memcpy(dst, src, n)
+    }
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.0.sol_memmove(dst, src, n)
+    }
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize, result: *mut i32) {
+        self.0.sol_memcmp(s1, s2, n, result)
+    }
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) {
+        self.0.sol_memset(s, c, n)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<String> = fields.iter().map(|field| BASE64.encode(field)).collect();
+        self.0
+            .sol_log(&format!("{EVENT_PREFIX}{}", fields.join(" ")))
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<SolInstruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+/// Placeholder held only while the real stubs are swapped out
+struct NoStubs;
+impl SyscallStubs for NoStubs {}
+
+/// How `EventLoggingStubs` marks event data in the logs
+const EVENT_PREFIX: &str = "event: ";
+
+fn entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    // The Anchor entrypoint ties the account slice to the infos' lifetime
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    program::entry(program_id, accounts, data)
+}
+
+/// Which mint the environment's rounds are denominated in
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MintKind {
+    Legacy,
+    Token2022,
+    /// Token-2022 mint charging this many basis points on every transfer
+    TransferFee(u16),
+    /// Wrapped SOL
+    Native,
+}
+
+pub struct Bettor {
+    pub key: Keypair,
+    pub tokens: Pubkey,
+}
+
+impl Bettor {
+    pub fn pubkey(&self) -> Pubkey {
+        self.key.pubkey()
+    }
+}
+
+/// Result of one transaction, with whatever the runtime recorded even on failure
+pub struct Tx {
+    pub result: Result<(), TransactionError>,
+    pub logs: Vec<String>,
+    pub return_data: Vec<u8>,
+}
+
+impl Tx {
+    #[track_caller]
+    pub fn ok(self) -> Self {
+        if let Err(err) = &self.result {
+            panic!("transaction failed: {err:?}\n{}", self.logs.join("\n"));
+        }
+        self
+    }
+
+    /// Custom error code the transaction failed with
+    #[track_caller]
+    pub fn code(&self) -> u32 {
+        match &self.result {
+            Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => *code,
+            other => panic!(
+                "expected a custom error, got {other:?}\n{}",
+                self.logs.join("\n")
+            ),
+        }
+    }
+
+    #[track_caller]
+    pub fn expect_err(&self, err: impl Into<u32>) {
+        assert_eq!(self.code(), err.into(), "\n{}", self.logs.join("\n"));
+    }
+
+    /// Events of type `E` the program emitted, in order
+    pub fn events<E: AnchorDeserialize + Discriminator>(&self) -> Vec<E> {
+        self.logs
+            .iter()
+            .filter_map(|log| {
+                log.strip_prefix("Program log: ")?
+                    .strip_prefix(EVENT_PREFIX)
+            })
+            .filter_map(|data| BASE64.decode(data).ok())
+            .filter(|data| data.starts_with(E::DISCRIMINATOR))
+            .map(|data| E::deserialize(&mut &data[E::DISCRIMINATOR.len()..]).unwrap())
+            .collect()
+    }
+
+    /// Decode the return data; the runtime trims its trailing zero bytes, so they're restored
+    pub fn returned<T: AnchorDeserialize>(&self) -> T {
+        let mut data = self.return_data.clone();
+        data.resize(MAX_RETURN_DATA, 0);
+        T::deserialize(&mut data.as_slice()).unwrap()
+    }
+}
+
+pub struct Env {
+    pub ctx: ProgramTestContext,
+    pub authority: Keypair,
+    pub treasury: Keypair,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub treasury_tokens: Pubkey,
+    nonce: u32,
+}
+
+pub fn round_pda(authority: &Pubkey, round_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"betting_round",
+            authority.as_ref(),
+            &round_id.to_le_bytes(),
+        ],
+        &program::ID,
+    )
+    .0
+}
+
+pub fn escrow_pda(authority: &Pubkey, round_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"escrow", authority.as_ref(), &round_id.to_le_bytes()],
+        &program::ID,
+    )
+    .0
+}
+
+pub fn bet_pda(authority: &Pubkey, round_id: u64, bettor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"bet",
+            authority.as_ref(),
+            &round_id.to_le_bytes(),
+            bettor.as_ref(),
+        ],
+        &program::ID,
+    )
+    .0
+}
+
+pub fn global_stats_pda(authority: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"global_stats", authority.as_ref()], &program::ID).0
+}
+
+pub fn user_profile_pda(bettor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"user_profile", bettor.as_ref()], &program::ID).0
+}
+
+/// PDA seeded by a name and the round account, e.g. the winners board or claim bitmap
+pub fn round_child_pda(seed: &[u8], round: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seed, round.as_ref()], &program::ID).0
+}
+
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: program::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn place_bet_args(prediction: BossPrediction) -> program::instruction::PlaceBet {
+    program::instruction::PlaceBet {
+        prediction,
+        username: String::from("player"),
+        memo: String::new(),
+    }
+}
+
+/// Mark `key` as a signer of `ix`, for accounts the program checks with `is_signer` itself
+pub fn signed_by(mut ix: Instruction, key: &Pubkey) -> Instruction {
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = true;
+    }
+    ix
+}
+
+impl Env {
+    pub async fn new() -> Self {
+        Self::with_mint(MintKind::Legacy).await
+    }
+
+    pub async fn with_mint(kind: MintKind) -> Self {
+        let mut pt = ProgramTest::new("boss_fight_betting", program::ID, processor!(entry));
+        if kind == MintKind::Native {
+            let mut data = vec![0; spl_token::state::Mint::LEN];
+            spl_token::state::Mint {
+                decimals: 9,
+                is_initialized: true,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            pt.add_account(
+                spl_token::native_mint::ID,
+                Account {
+                    lamports: Rent::default().minimum_balance(data.len()),
+                    data,
+                    owner: spl_token::ID,
+                    ..Default::default()
+                },
+            );
+        }
+        let ctx = pt.start_with_context().await;
+        // Program-test installs its stubs on first start; wrap them exactly once
+        static WRAP_STUBS: Once = Once::new();
+        WRAP_STUBS.call_once(|| {
+            let stubs = set_syscall_stubs(Box::new(NoStubs));
+            set_syscall_stubs(Box::new(EventLoggingStubs(stubs)));
+        });
+        let mut env = Env {
+            ctx,
+            authority: Keypair::new(),
+            treasury: Keypair::new(),
+            mint: Pubkey::default(),
+            token_program: match kind {
+                MintKind::Legacy | MintKind::Native => spl_token::ID,
+                _ => spl_token_2022::ID,
+            },
+            treasury_tokens: Pubkey::default(),
+            nonce: 0,
+        };
+        env.warp(START).await;
+        let (authority, treasury) = (env.authority.pubkey(), env.treasury.pubkey());
+        env.airdrop(&authority, 100_000_000_000).await;
+        env.airdrop(&treasury, 10_000_000_000).await;
+
+        env.mint = match kind {
+            MintKind::Native => spl_token::native_mint::ID,
+            MintKind::TransferFee(bps) => env.create_mint(Some(bps)).await,
+            _ => env.create_mint(None).await,
+        };
+        env.treasury_tokens = env.token_account(&treasury).await;
+        if kind == MintKind::Native {
+            env.wrap_sol(&env.treasury_tokens.clone(), TREASURY_SUPPLY)
+                .await;
+        } else {
+            env.mint_to(&env.treasury_tokens.clone(), TREASURY_SUPPLY)
+                .await;
+        }
+
+        let authority_key = env.authority.insecure_clone();
+        env.init_global_stats(&authority_key).await;
+        env
+    }
+
+    async fn create_mint(&mut self, transfer_fee_bps: Option<u16>) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.ctx.payer.pubkey();
+        let len = match transfer_fee_bps {
+            Some(_) => ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+                ExtensionType::TransferFeeConfig,
+            ])
+            .unwrap(),
+            None => spl_token::state::Mint::LEN,
+        };
+        let mut ixs = vec![system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            Rent::default().minimum_balance(len),
+            len as u64,
+            &self.token_program,
+        )];
+        if let Some(bps) = transfer_fee_bps {
+            ixs.push(
+                transfer_fee::instruction::initialize_transfer_fee_config(
+                    &self.token_program,
+                    &mint.pubkey(),
+                    Some(&payer),
+                    Some(&payer),
+                    bps,
+                    u64::MAX,
+                )
+                .unwrap(),
+            );
+        }
+        ixs.push(
+            spl_token_2022::instruction::initialize_mint2(
+                &self.token_program,
+                &mint.pubkey(),
+                &payer,
+                None,
+                DECIMALS,
+            )
+            .unwrap(),
+        );
+        self.send(&ixs, &[&mint]).await.ok();
+        mint.pubkey()
+    }
+
+    /// Canonical associated token account for `owner`, created if needed
+    pub async fn token_account(&mut self, owner: &Pubkey) -> Pubkey {
+        let address =
+            get_associated_token_address_with_program_id(owner, &self.mint, &self.token_program);
+        if self.account(&address).await.is_none() {
+            let ix = spl_associated_token_account::instruction::create_associated_token_account(
+                &self.ctx.payer.pubkey(),
+                owner,
+                &self.mint,
+                &self.token_program,
+            );
+            self.send(&[ix], &[]).await.ok();
+        }
+        address
+    }
+
+    /// Token account for `owner` at a fresh, non-associated address
+    pub async fn keypair_token_account(&mut self, owner: &Pubkey) -> Pubkey {
+        let account = Keypair::new();
+        let len = match self.token_program == spl_token::ID {
+            true => spl_token::state::Account::LEN,
+            false => ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+                ExtensionType::TransferFeeAmount,
+            ])
+            .unwrap(),
+        };
+        let ixs = [
+            system_instruction::create_account(
+                &self.ctx.payer.pubkey(),
+                &account.pubkey(),
+                Rent::default().minimum_balance(len),
+                len as u64,
+                &self.token_program,
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &self.token_program,
+                &account.pubkey(),
+                &self.mint,
+                owner,
+            )
+            .unwrap(),
+        ];
+        self.send(&ixs, &[&account]).await.ok();
+        account.pubkey()
+    }
+
+    pub async fn mint_to(&mut self, account: &Pubkey, amount: u64) {
+        let ix = spl_token_2022::instruction::mint_to(
+            &self.token_program,
+            &self.mint,
+            account,
+            &self.ctx.payer.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.send(&[ix], &[]).await.ok();
+    }
+
+    async fn wrap_sol(&mut self, account: &Pubkey, amount: u64) {
+        let ixs = [
+            system_instruction::transfer(&self.ctx.payer.pubkey(), account, amount),
+            spl_token::instruction::sync_native(&spl_token::ID, account).unwrap(),
+        ];
+        self.send(&ixs, &[]).await.ok();
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        let ix = system_instruction::transfer(&self.ctx.payer.pubkey(), to, lamports);
+        self.send(&[ix], &[]).await.ok();
+    }
+
+    /// A funded wallet with an associated token account for the round mint
+    pub async fn bettor(&mut self) -> Bettor {
+        let key = Keypair::new();
+        self.airdrop(&key.pubkey(), 1_000_000_000).await;
+        let tokens = self.token_account(&key.pubkey()).await;
+        Bettor { key, tokens }
+    }
+
+    /// A second operator with its own global stats, sharing the environment's treasury
+    pub async fn other_authority(&mut self) -> Keypair {
+        let authority = Keypair::new();
+        self.airdrop(&authority.pubkey(), 10_000_000_000).await;
+        self.init_global_stats(&authority).await;
+        authority
+    }
+
+    async fn init_global_stats(&mut self, authority: &Keypair) {
+        let ix = ix(
+            program::accounts::InitializeGlobalStats {
+                global_stats: global_stats_pda(&authority.pubkey()),
+                authority: authority.pubkey(),
+                system_program: anchor_lang::system_program::ID,
+            },
+            program::instruction::InitializeGlobalStats { round_cooldown: 0 },
+        );
+        self.send(&[ix], &[authority]).await.ok();
+    }
+
+    /// Sign with the fee payer plus whichever of `signers` the message needs. A compute
+    /// budget instruction with a per-call limit keeps repeated transactions distinct; it goes
+    /// first so it doesn't clear the return data of the last instruction.
+    pub async fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> Tx {
+        self.nonce += 1;
+        let mut ixs = ixs.to_vec();
+        ixs.insert(
+            0,
+            ComputeBudgetInstruction::set_compute_unit_limit(1_400_000 - self.nonce),
+        );
+        let payer = self.ctx.payer.insecure_clone();
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
+        let required =
+            &tx.message.account_keys[..tx.message.header.num_required_signatures as usize];
+        let mut keys: Vec<&Keypair> = vec![&payer];
+        for signer in signers {
+            if required.contains(&signer.pubkey())
+                && !keys.iter().any(|k| k.pubkey() == signer.pubkey())
+            {
+                keys.push(signer);
+            }
+        }
+        tx.sign(&keys, self.ctx.last_blockhash);
+        let outcome = self
+            .ctx
+            .banks_client
+            .process_transaction_with_metadata(tx)
+            .await
+            .unwrap();
+        let (logs, return_data) = match outcome.metadata {
+            Some(metadata) => (
+                metadata.log_messages,
+                metadata.return_data.map(|r| r.data).unwrap_or_default(),
+            ),
+            None => (Vec::new(), Vec::new()),
+        };
+        Tx {
+            result: outcome.result,
+            logs,
+            return_data,
+        }
+    }
+
+    /// `send` with the authority and treasury available as signers
+    pub async fn send_by_authority(&mut self, ixs: &[Instruction]) -> Tx {
+        let (authority, treasury) = (
+            self.authority.insecure_clone(),
+            self.treasury.insecure_clone(),
+        );
+        self.send(ixs, &[&authority, &treasury]).await
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.clock().await.unix_timestamp
+    }
+
+    async fn clock(&mut self) -> Clock {
+        self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap()
+    }
+
+    pub async fn warp(&mut self, unix_timestamp: i64) {
+        let clock = Clock {
+            unix_timestamp,
+            ..self.clock().await
+        };
+        self.ctx.set_sysvar(&clock);
+    }
+
+    pub async fn account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.ctx.banks_client.get_account(*address).await.unwrap()
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.account(address).await.map_or(0, |a| a.lamports)
+    }
+
+    pub async fn state<T: AccountDeserialize>(&mut self, address: &Pubkey) -> Option<T> {
+        let account = self.account(address).await?;
+        Some(T::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    /// Rewrite a program account in place, for setting up states instructions can't reach
+    pub async fn overwrite<T: anchor_lang::AccountSerialize>(
+        &mut self,
+        address: &Pubkey,
+        value: &T,
+    ) {
+        let account = self.account(address).await.unwrap();
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+        data.resize(account.data.len(), 0);
+        self.ctx.set_account(
+            address,
+            &AccountSharedData::from(Account { data, ..account }),
+        );
+    }
+
+    pub async fn round(&mut self, round_id: u64) -> BettingRound {
+        let address = self.round_key(round_id);
+        self.state(&address).await.unwrap()
+    }
+
+    pub async fn bet_state(&mut self, round_id: u64, bettor: &Pubkey) -> Option<BetAccount> {
+        let address = self.bet_key(round_id, bettor);
+        self.state(&address).await
+    }
+
+    pub async fn global_stats(&mut self) -> GlobalStats {
+        let address = global_stats_pda(&self.authority.pubkey());
+        self.state(&address).await.unwrap()
+    }
+
+    pub async fn balance(&mut self, token_account: &Pubkey) -> u64 {
+        let account = self.account(token_account).await.unwrap();
+        spl_token::state::Account::unpack_from_slice(
+            &account.data[..spl_token::state::Account::LEN],
+        )
+        .unwrap()
+        .amount
+    }
+
+    pub fn round_key(&self, round_id: u64) -> Pubkey {
+        round_pda(&self.authority.pubkey(), round_id)
+    }
+
+    pub fn escrow_key(&self, round_id: u64) -> Pubkey {
+        escrow_pda(&self.authority.pubkey(), round_id)
+    }
+
+    pub fn bet_key(&self, round_id: u64, bettor: &Pubkey) -> Pubkey {
+        bet_pda(&self.authority.pubkey(), round_id, bettor)
+    }
+
+    pub fn init_round_accounts(
+        &self,
+        authority: &Pubkey,
+        round_id: u64,
+    ) -> program::accounts::InitializeBettingRound {
+        program::accounts::InitializeBettingRound {
+            betting_round: round_pda(authority, round_id),
+            escrow_token_account: escrow_pda(authority, round_id),
+            token_mint: self.mint,
+            treasury_token_account: self.treasury_tokens,
+            authority: *authority,
+            treasury: self.treasury.pubkey(),
+            global_stats: global_stats_pda(authority),
+            system_program: anchor_lang::system_program::ID,
+            token_program: self.token_program,
+            rent: solana_sdk::sysvar::rent::ID,
+        }
+    }
+
+    /// `initialize_betting_round` with the default durations, HP and pool
+    pub fn init_round_ix(&self, round_id: u64, config: RoundConfig) -> Instruction {
+        self.init_round_ix_with(round_id, POOL, config)
+    }
+
+    pub fn init_round_ix_with(
+        &self,
+        round_id: u64,
+        prize_pool_amount: u64,
+        config: RoundConfig,
+    ) -> Instruction {
+        let ix = ix(
+            self.init_round_accounts(&self.authority.pubkey(), round_id),
+            program::instruction::InitializeBettingRound {
+                round_id,
+                betting_duration: BETTING,
+                fight_duration: FIGHT,
+                initial_hp: HP,
+                prize_pool_amount,
+                config,
+            },
+        );
+        signed_by(ix, &self.treasury.pubkey())
+    }
+
+    pub async fn create_round(&mut self, round_id: u64, config: RoundConfig) -> Tx {
+        let ix = self.init_round_ix(round_id, config);
+        self.send_by_authority(&[ix]).await
+    }
+
+    pub fn place_bet_accounts(
+        &self,
+        round_id: u64,
+        bettor: &Pubkey,
+    ) -> program::accounts::PlaceBet {
+        program::accounts::PlaceBet {
+            betting_round: self.round_key(round_id),
+            bet_account: self.bet_key(round_id, bettor),
+            bettor: *bettor,
+            payer: *bettor,
+            user_profile: None,
+            claim_bitmap: None,
+            token_mint: None,
+            blocklist: None,
+            system_program: anchor_lang::system_program::ID,
+        }
+    }
+
+    pub fn place_bet_ix(
+        &self,
+        round_id: u64,
+        bettor: &Pubkey,
+        prediction: BossPrediction,
+    ) -> Instruction {
+        ix(
+            self.place_bet_accounts(round_id, bettor),
+            place_bet_args(prediction),
+        )
+    }
+
+    pub async fn bet(&mut self, round_id: u64, bettor: &Bettor, prediction: BossPrediction) -> Tx {
+        let ix = self.place_bet_ix(round_id, &bettor.pubkey(), prediction);
+        self.send(&[ix], &[&bettor.key]).await
+    }
+
+    /// Fresh bettors, one per prediction, each with a bet on `round_id`
+    pub async fn bettors(&mut self, round_id: u64, predictions: &[BossPrediction]) -> Vec<Bettor> {
+        let mut bettors = Vec::new();
+        for prediction in predictions {
+            let bettor = self.bettor().await;
+            self.bet(round_id, &bettor, prediction.clone()).await.ok();
+            bettors.push(bettor);
+        }
+        bettors
+    }
+
+    pub async fn start_fight(&mut self, round_id: u64) -> Tx {
+        let ix = ix(
+            program::accounts::StartFightPhase {
+                betting_round: self.round_key(round_id),
+                authority: self.authority.pubkey(),
+            },
+            program::instruction::StartFightPhase {
+                bets_merkle_root: [0; 32],
+            },
+        );
+        self.send_by_authority(&[ix]).await
+    }
+
+    pub fn update_hp_ix(&self, round_id: u64, operator: &Pubkey, new_hp: u32) -> Instruction {
+        ix(
+            program::accounts::UpdateBossHp {
+                betting_round: self.round_key(round_id),
+                operator: *operator,
+            },
+            program::instruction::UpdateBossHp { new_hp },
+        )
+    }
+
+    pub fn end_fight_ix(&self, round_id: u64, final_hp: u64) -> Instruction {
+        ix(
+            program::accounts::EndFight {
+                betting_round: self.round_key(round_id),
+                escrow_token_account: self.escrow_key(round_id),
+                operator: self.authority.pubkey(),
+            },
+            program::instruction::EndFight {
+                final_hp,
+                outcome_attestation: [0; 32],
+                winning_predictions: 0,
+            },
+        )
+    }
+
+    pub async fn end_fight(&mut self, round_id: u64, final_hp: u64) -> Tx {
+        let ix = self.end_fight_ix(round_id, final_hp);
+        self.send_by_authority(&[ix]).await
+    }
+
+    /// Close betting, start the fight and end it at `final_hp` once the fight window is over
+    pub async fn fight_to_end(&mut self, round_id: u64, final_hp: u64) -> Tx {
+        let close = self.round(round_id).await.betting_close_time().unwrap();
+        self.warp(close).await;
+        self.start_fight(round_id).await.ok();
+        let fight_end = self.round(round_id).await.fight_end_time;
+        self.warp(fight_end).await;
+        self.end_fight(round_id, final_hp).await
+    }
+
+    pub fn claim_accounts(&self, round_id: u64, bettor: &Bettor) -> program::accounts::ClaimPayout {
+        program::accounts::ClaimPayout {
+            betting_round: self.round_key(round_id),
+            bet_account: self.bet_key(round_id, &bettor.pubkey()),
+            escrow_token_account: self.escrow_key(round_id),
+            token_mint: self.mint,
+            bettor_token_account: bettor.tokens,
+            bettor: bettor.pubkey(),
+            rent_payer: bettor.pubkey(),
+            global_stats: None,
+            winners_board: None,
+            claim_bitmap: None,
+            user_profile: None,
+            token_program: self.token_program,
+        }
+    }
+
+    pub async fn claim(&mut self, round_id: u64, bettor: &Bettor) -> Tx {
+        let ix = ix(
+            self.claim_accounts(round_id, bettor),
+            program::instruction::ClaimPayout { unwrap_sol: false },
+        );
+        self.send(&[ix], &[]).await
+    }
+
+    pub fn close_round_ix(&self, round_id: u64) -> Instruction {
+        ix(
+            program::accounts::CloseBettingRound {
+                betting_round: self.round_key(round_id),
+                escrow_token_account: self.escrow_key(round_id),
+                token_mint: Some(self.mint),
+                treasury_token_account: Some(self.treasury_tokens),
+                authority: self.authority.pubkey(),
+                global_stats: Some(global_stats_pda(&self.authority.pubkey())),
+                token_program: self.token_program,
+            },
+            program::instruction::CloseBettingRound {},
+        )
+    }
+
+    pub async fn close_round(&mut self, round_id: u64) -> Tx {
+        let ix = self.close_round_ix(round_id);
+        self.send_by_authority(&[ix]).await
+    }
+}
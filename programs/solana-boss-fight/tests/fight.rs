@@ -0,0 +1,245 @@
+mod common;
+
+use boss_fight_betting::{
+    self as program, BettingError, BossPrediction, FightEnded, OperatorNote, RoundConfig,
+};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+};
+
+#[tokio::test]
+async fn outcome_attestation_round_trips_through_account_and_event() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+
+    let attestation = [7; 32];
+    let end = ix(
+        program::accounts::EndFight {
+            betting_round: env.round_key(1),
+            escrow_token_account: env.escrow_key(1),
+            operator: env.authority.pubkey(),
+        },
+        program::instruction::EndFight {
+            final_hp: 0,
+            outcome_attestation: attestation,
+            winning_predictions: 0,
+        },
+    );
+    let ended = env.send_by_authority(&[end]).await.ok();
+
+    assert_eq!(env.round(1).await.outcome_attestation, attestation);
+    let events = ended.events::<FightEnded>();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].outcome_attestation, attestation);
+    assert!(events[0].boss_defeated);
+}
+
+#[tokio::test]
+async fn operator_runs_the_fight_while_the_authority_keeps_admin() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let operator = Keypair::new();
+    let hand_over = ix(
+        program::accounts::SetOperator {
+            betting_round: env.round_key(1),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::SetOperator {
+            operator: operator.pubkey(),
+        },
+    );
+    env.send_by_authority(std::slice::from_ref(&hand_over))
+        .await
+        .ok();
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+
+    let by_authority = env.update_hp_ix(1, &env.authority.pubkey(), 50);
+    env.send_by_authority(&[by_authority])
+        .await
+        .expect_err(BettingError::Unauthorized);
+    let by_operator = env.update_hp_ix(1, &operator.pubkey(), 50);
+    env.send(&[by_operator], &[&operator]).await.ok();
+    assert_eq!(env.round(1).await.current_hp, 50);
+
+    let mut take_over = hand_over;
+    take_over.accounts[1].pubkey = operator.pubkey();
+    env.send(&[take_over], &[&operator])
+        .await
+        .expect_err(BettingError::Unauthorized);
+
+    env.warp(START + BETTING + FIGHT).await;
+    env.end_fight(1, 50)
+        .await
+        .expect_err(BettingError::Unauthorized);
+    let mut end = env.end_fight_ix(1, 50);
+    end.accounts[2].pubkey = operator.pubkey();
+    env.send(&[end], &[&operator]).await.ok();
+
+    let open_claims = program::accounts::OpenClaims {
+        betting_round: env.round_key(1),
+        authority: operator.pubkey(),
+    };
+    let open = ix(open_claims, program::instruction::OpenClaims {});
+    env.send(&[open], &[&operator])
+        .await
+        .expect_err(BettingError::Unauthorized);
+}
+
+#[tokio::test]
+async fn amended_outcome_rechecks_the_escrow_against_the_new_obligation() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    env.bettors(1, &[BossPrediction::Death, BossPrediction::Survival])
+        .await;
+    env.fight_to_end(1, 0).await.ok();
+    assert!(!env.round(1).await.underfunded);
+
+    // A payout pool over what the escrow holds, as a stand-in for an escrow drained since
+    let mut round = env.round(1).await;
+    round.payout_pool = 2 * POOL;
+    env.overwrite(&env.round_key(1), &round).await;
+
+    let amend = ix(
+        program::accounts::AmendOutcome {
+            betting_round: env.round_key(1),
+            escrow_token_account: env.escrow_key(1),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::AmendOutcome {
+            final_hp: 50,
+            outcome_attestation: [0; 32],
+            winning_predictions: 0,
+        },
+    );
+    env.send_by_authority(&[amend]).await.ok();
+    let round = env.round(1).await;
+    assert!(round.is_winning(&BossPrediction::Survival));
+    assert_eq!(round.outstanding_obligation, 2 * POOL);
+    assert!(round.underfunded);
+}
+
+#[tokio::test]
+async fn end_fight_flags_a_round_whose_escrow_is_short() {
+    let mut env = Env::new().await;
+    for round_id in [1, 2] {
+        env.create_round(round_id, RoundConfig::default())
+            .await
+            .ok();
+        env.bettors(round_id, &[BossPrediction::Death]).await;
+    }
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    env.start_fight(2).await.ok();
+
+    // A prize pool over what the escrow holds, as a stand-in for an escrow drained mid-fight
+    let mut drained = env.round(2).await;
+    drained.prize_pool_amount = 2 * POOL;
+    env.overwrite(&env.round_key(2), &drained).await;
+
+    env.warp(START + BETTING + FIGHT).await;
+    env.end_fight(1, 0).await.ok();
+    env.end_fight(2, 0).await.ok();
+    assert!(!env.round(1).await.underfunded);
+    let round = env.round(2).await;
+    assert_eq!(round.outstanding_obligation, 2 * POOL);
+    assert!(round.underfunded);
+}
+
+#[tokio::test]
+async fn fight_ended_names_the_winning_prediction_for_both_outcomes() {
+    let mut env = Env::new().await;
+    for round_id in [1, 2] {
+        env.create_round(round_id, RoundConfig::default())
+            .await
+            .ok();
+    }
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    env.start_fight(2).await.ok();
+    env.warp(START + BETTING + FIGHT).await;
+
+    for (round_id, final_hp, dead) in [(1, 0, true), (2, 50, false)] {
+        let ended = env.end_fight(round_id, final_hp).await.ok();
+        let event = &ended.events::<FightEnded>()[0];
+        assert_eq!(event.boss_defeated, dead);
+        assert!(event.winning_prediction == BossPrediction::outcome(dead));
+        let round = env.round(round_id).await;
+        assert!(round.winning_prediction == event.winning_prediction);
+    }
+    assert!(env.round(1).await.winning_prediction == BossPrediction::Death);
+    assert!(env.round(2).await.winning_prediction == BossPrediction::Survival);
+}
+
+fn operator_note_ix(env: &Env, round_id: u64, note: &str) -> Instruction {
+    ix(
+        program::accounts::SetOperatorNote {
+            betting_round: env.round_key(round_id),
+            operator: env.authority.pubkey(),
+        },
+        program::instruction::SetOperatorNote {
+            note: note.to_string(),
+        },
+    )
+}
+
+#[tokio::test]
+async fn operator_note_is_stored_and_announced_during_the_fight() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let too_early = operator_note_ix(&env, 1, "warming up");
+    env.send_by_authority(&[too_early])
+        .await
+        .expect_err(BettingError::NotInFightPhase);
+
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    let note = operator_note_ix(&env, 1, "Phase 2\nstarted!");
+    let sent = env.send_by_authority(&[note]).await.ok();
+    let event = &sent.events::<OperatorNote>()[0];
+    assert_eq!(event.round_id, 1);
+    assert_eq!(event.note, "Phase 2started!");
+    assert_eq!(env.round(1).await.operator_note, "Phase 2started!");
+
+    let too_long = operator_note_ix(&env, 1, &"x".repeat(65));
+    env.send_by_authority(&[too_long])
+        .await
+        .expect_err(BettingError::NoteTooLong);
+    assert_eq!(env.round(1).await.operator_note, "Phase 2started!");
+}
+
+#[tokio::test]
+async fn fight_end_time_takes_a_last_hp_update_and_the_end() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    env.create_round(2, RoundConfig::default()).await.ok();
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    env.start_fight(2).await.ok();
+    let fight_end = env.round(1).await.fight_end_time;
+
+    env.warp(fight_end - 1).await;
+    env.end_fight(1, 30)
+        .await
+        .expect_err(BettingError::FightNotFinished);
+
+    // The boundary second itself allows both
+    env.warp(fight_end).await;
+    let last_update = env.update_hp_ix(1, &env.authority.pubkey(), 30);
+    env.send_by_authority(&[last_update]).await.ok();
+    env.end_fight(1, 30).await.ok();
+    let round = env.round(1).await;
+    assert_eq!(round.current_hp, 30);
+    assert!(round.winning_prediction == BossPrediction::Survival);
+
+    env.warp(fight_end + 1).await;
+    let too_late = env.update_hp_ix(2, &env.authority.pubkey(), 30);
+    env.send_by_authority(&[too_late])
+        .await
+        .expect_err(BettingError::FightPeriodExpired);
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use anchor_lang::solana_program::hash::hashv;
+use boss_fight_betting::{
+    self as program, merkle_bet_leaf, BettingError, BossPrediction, RoundConfig,
+};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::{instruction::Instruction, signature::Signer};
+
+fn merkle_node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[1u8], &left, &right]).to_bytes()
+}
+
+fn claim_merkle_ix(
+    env: &Env,
+    bettor: &Bettor,
+    index: u64,
+    prediction: BossPrediction,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let round = env.round_key(1);
+    ix(
+        program::accounts::ClaimPayoutMerkle {
+            betting_round: round,
+            merkle_claims: round_child_pda(b"merkle_claims", &round),
+            escrow_token_account: env.escrow_key(1),
+            token_mint: env.mint,
+            bettor_token_account: bettor.tokens,
+            bettor: bettor.pubkey(),
+            token_program: env.token_program,
+        },
+        program::instruction::ClaimPayoutMerkle {
+            index,
+            prediction,
+            proof,
+        },
+    )
+}
+
+#[tokio::test]
+async fn merkle_winners_claim_once_with_a_valid_proof() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        merkle_bets: true,
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let round = env.round_key(1);
+    let init_claims = ix(
+        program::accounts::InitializeMerkleClaims {
+            betting_round: round,
+            merkle_claims: round_child_pda(b"merkle_claims", &round),
+            authority: env.authority.pubkey(),
+            system_program: anchor_lang::system_program::ID,
+        },
+        program::instruction::InitializeMerkleClaims {},
+    );
+    env.send_by_authority(&[init_claims]).await.ok();
+
+    // Two Death bets and one Survival bet, committed off-chain
+    let (alice, bob, carol) = (env.bettor().await, env.bettor().await, env.bettor().await);
+    let leaves = [
+        merkle_bet_leaf(0, &alice.pubkey(), &BossPrediction::Death),
+        merkle_bet_leaf(1, &bob.pubkey(), &BossPrediction::Death),
+        merkle_bet_leaf(2, &carol.pubkey(), &BossPrediction::Survival),
+    ];
+    let left = merkle_node(leaves[0], leaves[1]);
+    let root = merkle_node(left, leaves[2]);
+
+    env.warp(START + BETTING).await;
+    let commit = ix(
+        program::accounts::CommitMerkleBets {
+            betting_round: round,
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::CommitMerkleBets {
+            bets_merkle_root: root,
+            death_bets: 2,
+            survival_bets: 1,
+        },
+    );
+    let start = ix(
+        program::accounts::StartFightPhase {
+            betting_round: round,
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::StartFightPhase {
+            bets_merkle_root: root,
+        },
+    );
+    env.send_by_authority(&[commit, start]).await.ok();
+    env.warp(START + BETTING + FIGHT).await;
+    env.end_fight(1, 0).await.ok();
+
+    let claim = claim_merkle_ix(
+        &env,
+        &alice,
+        0,
+        BossPrediction::Death,
+        vec![leaves[1], leaves[2]],
+    );
+    env.send(std::slice::from_ref(&claim), &[]).await.ok();
+    assert_eq!(env.balance(&alice.tokens).await, POOL / 2);
+    env.send(&[claim], &[])
+        .await
+        .expect_err(BettingError::PayoutAlreadyClaimed);
+
+    for bad in [
+        claim_merkle_ix(&env, &bob, 1, BossPrediction::Death, vec![leaves[0]]),
+        claim_merkle_ix(
+            &env,
+            &bob,
+            0,
+            BossPrediction::Death,
+            vec![leaves[1], leaves[2]],
+        ),
+        claim_merkle_ix(
+            &env,
+            &bob,
+            1,
+            BossPrediction::Survival,
+            vec![leaves[0], leaves[2]],
+        ),
+    ] {
+        env.send(&[bad], &[])
+            .await
+            .expect_err(BettingError::InvalidMerkleProof);
+    }
+    let loser = claim_merkle_ix(&env, &carol, 2, BossPrediction::Survival, vec![left]);
+    env.send(&[loser], &[])
+        .await
+        .expect_err(BettingError::BetLost);
+
+    let claim = claim_merkle_ix(
+        &env,
+        &bob,
+        1,
+        BossPrediction::Death,
+        vec![leaves[0], leaves[2]],
+    );
+    env.send(&[claim], &[]).await.ok();
+    assert_eq!(env.balance(&bob.tokens).await, POOL / 2);
+    assert_eq!(env.balance(&env.escrow_key(1)).await, 0);
+}
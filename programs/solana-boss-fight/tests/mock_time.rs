@@ -0,0 +1,56 @@
+mod common;
+
+use boss_fight_betting::{self as program, BettingError, BossPrediction, GamePhase, RoundConfig};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::{instruction::Instruction, signature::Signer};
+
+fn set_mock_time_ix(env: &Env, round_id: u64, mock_time: i64) -> Instruction {
+    ix(
+        program::accounts::SetMockTime {
+            betting_round: env.round_key(round_id),
+            authority: env.authority.pubkey(),
+        },
+        program::instruction::SetMockTime { mock_time },
+    )
+}
+
+#[tokio::test]
+async fn mock_time_moves_a_round_past_betting_without_the_clock() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    env.bettors(1, &[BossPrediction::Death]).await;
+    env.start_fight(1)
+        .await
+        .expect_err(BettingError::BettingStillActive);
+
+    let past_betting = set_mock_time_ix(&env, 1, START + BETTING + 1);
+    env.send_by_authority(&[past_betting]).await.ok();
+    let late = env.bettor().await;
+    env.bet(1, &late, BossPrediction::Death)
+        .await
+        .expect_err(BettingError::BettingPeriodExpired);
+    env.start_fight(1).await.ok();
+    assert!(env.round(1).await.phase == GamePhase::Fighting);
+    assert_eq!(env.now().await, START);
+
+    // Back on the real clock the fight is nowhere near over
+    let real_clock = set_mock_time_ix(&env, 1, 0);
+    env.send_by_authority(&[real_clock]).await.ok();
+    env.end_fight(1, 50)
+        .await
+        .expect_err(BettingError::FightNotFinished);
+}
+
+#[tokio::test]
+async fn only_the_authority_can_mock_a_rounds_clock() {
+    let mut env = Env::new().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let stranger = env.bettor().await;
+    let mut mock = set_mock_time_ix(&env, 1, START + BETTING);
+    mock.accounts[1].pubkey = stranger.pubkey();
+    env.send(&[mock], &[&stranger.key])
+        .await
+        .expect_err(BettingError::Unauthorized);
+    assert_eq!(env.round(1).await.mock_time, 0);
+}
@@ -0,0 +1,590 @@
+mod common;
+
+use boss_fight_betting::{
+    self as program, BettingError, BettingRound, BettingRoundInitialized, BossPrediction,
+    FightEnded, RoundConfig, RoundSchedule, MAX_ROUND_SERIES,
+};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signer,
+};
+
+#[tokio::test]
+async fn same_round_id_under_two_authorities_creates_two_rounds() {
+    let mut env = Env::new().await;
+    let other = env.other_authority().await;
+
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let ix = signed_by(
+        ix(
+            env.init_round_accounts(&other.pubkey(), 1),
+            program::instruction::InitializeBettingRound {
+                round_id: 1,
+                betting_duration: BETTING,
+                fight_duration: FIGHT,
+                initial_hp: HP,
+                prize_pool_amount: 2 * POOL,
+                config: RoundConfig::default(),
+            },
+        ),
+        &env.treasury.pubkey(),
+    );
+    let treasury = env.treasury.insecure_clone();
+    env.send(&[ix], &[&other, &treasury]).await.ok();
+
+    let mine = env.round(1).await;
+    let theirs: BettingRound = env.state(&round_pda(&other.pubkey(), 1)).await.unwrap();
+    assert_eq!(mine.authority, env.authority.pubkey());
+    assert_eq!(theirs.authority, other.pubkey());
+    assert_ne!(mine.escrow, theirs.escrow);
+    assert_eq!(env.balance(&mine.escrow).await, POOL);
+    assert_eq!(env.balance(&theirs.escrow).await, 2 * POOL);
+}
+
+#[tokio::test]
+async fn global_stats_aggregate_funding_and_payouts_across_rounds() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config.clone()).await.ok();
+    let create = env.init_round_ix_with(2, 500, config);
+    env.send_by_authority(&[create]).await.ok();
+
+    let stats = env.global_stats().await;
+    assert_eq!(stats.rounds_created, 2);
+    assert_eq!(stats.total_pool_funded, POOL + 500);
+    assert_eq!(stats.total_paid_out, 0);
+
+    let winners = [
+        env.bettors(1, &[BossPrediction::Death]).await,
+        env.bettors(2, &[BossPrediction::Death]).await,
+    ];
+    for (round_id, bettors) in [1, 2].into_iter().zip(&winners) {
+        env.fight_to_end(round_id, 0).await.ok();
+        let mut accounts = env.claim_accounts(round_id, &bettors[0]);
+        accounts.global_stats = Some(global_stats_pda(&env.authority.pubkey()));
+        let claim = ix(
+            accounts,
+            program::instruction::ClaimPayout { unwrap_sol: false },
+        );
+        env.send(&[claim], &[]).await.ok();
+    }
+
+    let stats = env.global_stats().await;
+    assert_eq!(stats.rounds_created, 2);
+    assert_eq!(stats.total_paid_out, POOL + 500);
+}
+
+fn update_global_stats(env: &Env, data: impl anchor_lang::InstructionData) -> Instruction {
+    ix(
+        program::accounts::UpdateGlobalStats {
+            global_stats: global_stats_pda(&env.authority.pubkey()),
+            authority: env.authority.pubkey(),
+        },
+        data,
+    )
+}
+
+#[tokio::test]
+async fn back_to_back_rounds_wait_out_the_authority_cooldown() {
+    let mut env = Env::new().await;
+    let set_cooldown = update_global_stats(
+        &env,
+        program::instruction::SetRoundCooldown {
+            round_cooldown: 100,
+        },
+    );
+    env.send_by_authority(&[set_cooldown]).await.ok();
+
+    env.create_round(1, RoundConfig::default()).await.ok();
+    env.create_round(2, RoundConfig::default())
+        .await
+        .expect_err(BettingError::CooldownActive);
+    env.warp(START + 99).await;
+    env.create_round(2, RoundConfig::default())
+        .await
+        .expect_err(BettingError::CooldownActive);
+
+    env.warp(START + 100).await;
+    env.create_round(2, RoundConfig::default()).await.ok();
+    assert_eq!(env.global_stats().await.last_round_created_at, START + 100);
+}
+
+fn clone_round_ix(env: &Env, authority: &Pubkey, round_id: u64) -> Instruction {
+    let create = env.init_round_accounts(authority, round_id);
+    let clone = ix(
+        program::accounts::CloneRound {
+            source_round: env.round_key(1),
+            betting_round: create.betting_round,
+            escrow_token_account: create.escrow_token_account,
+            token_mint: create.token_mint,
+            treasury_token_account: create.treasury_token_account,
+            authority: create.authority,
+            treasury: create.treasury,
+            global_stats: create.global_stats,
+            system_program: create.system_program,
+            token_program: create.token_program,
+            rent: create.rent,
+        },
+        program::instruction::CloneRound {
+            round_id,
+            betting_duration: 120,
+            fight_duration: 60,
+            prize_pool_amount: 700,
+        },
+    );
+    signed_by(clone, &env.treasury.pubkey())
+}
+
+#[tokio::test]
+async fn cloned_round_copies_the_source_settings_with_fresh_timing() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        claim_window: 500,
+        top_k_winners: 3,
+        enforce_ata: true,
+        game_name: String::from("raid"),
+        ..Default::default()
+    };
+    env.create_round(1, config.clone()).await.ok();
+    env.warp(START + 10).await;
+    let clone = clone_round_ix(&env, &env.authority.pubkey(), 2);
+    env.send_by_authority(&[clone]).await.ok();
+
+    let source = env.round(1).await;
+    let cloned = env.round(2).await;
+    assert!(cloned.config == config);
+    assert_eq!(cloned.token_mint, source.token_mint);
+    assert_eq!(cloned.initial_hp, source.initial_hp);
+    assert_eq!(cloned.round_id, 2);
+    assert_eq!(cloned.betting_end_time, START + 10 + 120);
+    assert_eq!(cloned.fight_duration, 60);
+    assert_eq!(cloned.prize_pool_amount, 700);
+    assert_eq!(env.balance(&cloned.escrow).await, 700);
+}
+
+#[tokio::test]
+async fn another_authority_cannot_clone_a_round() {
+    let mut env = Env::new().await;
+    let other = env.other_authority().await;
+    env.create_round(1, RoundConfig::default()).await.ok();
+
+    let clone = clone_round_ix(&env, &other.pubkey(), 2);
+    let treasury = env.treasury.insecure_clone();
+    env.send(&[clone], &[&other, &treasury])
+        .await
+        .expect_err(BettingError::Unauthorized);
+}
+
+/// `ix` with every reference to `key` swapped for the all-zero pubkey
+fn with_default_key(mut ix: Instruction, key: &Pubkey) -> Instruction {
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.pubkey = Pubkey::default();
+        meta.is_signer = false;
+    }
+    ix
+}
+
+#[tokio::test]
+async fn default_pubkeys_are_rejected_at_creation_and_claim() {
+    let mut env = Env::new().await;
+    let create = env.init_round_ix(1, RoundConfig::default());
+    for key in [
+        env.authority.pubkey(),
+        env.treasury.pubkey(),
+        env.mint,
+        env.treasury_tokens,
+    ] {
+        let tx = env
+            .send_by_authority(&[with_default_key(create.clone(), &key)])
+            .await;
+        assert!(tx.result.is_err());
+        assert!(env.account(&env.round_key(1)).await.is_none());
+    }
+
+    let config = RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    let claim = ix(
+        env.claim_accounts(1, &bettors[0]),
+        program::instruction::ClaimPayout { unwrap_sol: false },
+    );
+    for key in [bettors[0].pubkey(), bettors[0].tokens] {
+        let tx = env
+            .send(&[with_default_key(claim.clone(), &key)], &[])
+            .await;
+        assert!(tx.result.is_err());
+    }
+    assert!(
+        !env.bet_state(1, &bettors[0].pubkey())
+            .await
+            .unwrap()
+            .payout_claimed
+    );
+    env.send(&[claim], &[]).await.ok();
+}
+
+#[tokio::test]
+async fn open_round_cap_frees_a_slot_when_a_round_closes() {
+    let mut env = Env::new().await;
+    let set_cap = update_global_stats(
+        &env,
+        program::instruction::SetMaxConcurrentRounds {
+            max_concurrent_rounds: 1,
+        },
+    );
+    env.send_by_authority(&[set_cap]).await.ok();
+
+    // An unfunded round with no bets ends with an empty escrow it can close on
+    let create = env.init_round_ix_with(1, 0, RoundConfig::default());
+    env.send_by_authority(&[create]).await.ok();
+    env.create_round(2, RoundConfig::default())
+        .await
+        .expect_err(BettingError::TooManyOpenRounds);
+    assert_eq!(env.global_stats().await.open_rounds, 1);
+
+    env.fight_to_end(1, 0).await.ok();
+    env.close_round(1).await.ok();
+    assert_eq!(env.global_stats().await.open_rounds, 0);
+    env.create_round(2, RoundConfig::default()).await.ok();
+    assert_eq!(env.global_stats().await.open_rounds, 1);
+}
+
+fn validate_params_ix(env: &Env, betting_duration: i64, prize_pool_amount: u64) -> Instruction {
+    ix(
+        program::accounts::ValidateRoundParams {
+            token_mint: env.mint,
+            treasury_token_account: env.treasury_tokens,
+            global_stats: Some(global_stats_pda(&env.authority.pubkey())),
+        },
+        program::instruction::ValidateRoundParams {
+            betting_duration,
+            fight_duration: FIGHT,
+            initial_hp: HP,
+            prize_pool_amount,
+            config: RoundConfig::default(),
+        },
+    )
+}
+
+#[tokio::test]
+async fn round_params_are_checked_without_creating_a_round() {
+    let mut env = Env::new().await;
+    let valid = validate_params_ix(&env, BETTING, POOL);
+    let schedule: RoundSchedule = env.send(&[valid], &[]).await.ok().returned();
+    assert!(
+        schedule
+            == RoundSchedule {
+                funding_end_time: START,
+                betting_start_time: START,
+                betting_end_time: START + BETTING,
+                fight_end_time: START + BETTING + FIGHT,
+            }
+    );
+
+    let short = validate_params_ix(&env, 1, POOL);
+    env.send(&[short], &[])
+        .await
+        .expect_err(BettingError::InvalidDuration);
+    let unaffordable = validate_params_ix(&env, BETTING, TREASURY_SUPPLY + 1);
+    env.send(&[unaffordable], &[])
+        .await
+        .expect_err(BettingError::InsufficientTreasuryFunds);
+
+    let set_cooldown = update_global_stats(
+        &env,
+        program::instruction::SetRoundCooldown {
+            round_cooldown: 100,
+        },
+    );
+    env.send_by_authority(&[set_cooldown]).await.ok();
+    env.create_round(1, RoundConfig::default()).await.ok();
+    let during_cooldown = validate_params_ix(&env, BETTING, POOL);
+    env.send(&[during_cooldown], &[])
+        .await
+        .expect_err(BettingError::CooldownActive);
+    assert!(env.account(&env.round_key(2)).await.is_none());
+}
+
+#[tokio::test]
+async fn rules_hash_is_announced_at_creation_and_kept_for_the_round() {
+    let mut env = Env::new().await;
+    let rules_hash = [7; 32];
+    let config = RoundConfig {
+        rules_hash,
+        ..Default::default()
+    };
+    let created = env.create_round(1, config).await.ok();
+    let initialized = created.events::<BettingRoundInitialized>();
+    assert_eq!(initialized[0].rules_hash, rules_hash);
+
+    env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    assert_eq!(env.round(1).await.config.rules_hash, rules_hash);
+}
+
+fn round_series_ix(env: &Env, base_round_id: u64, count: u8) -> Instruction {
+    let authority = env.authority.pubkey();
+    let mut series = ix(
+        program::accounts::InitializeRoundSeries {
+            token_mint: env.mint,
+            treasury_token_account: env.treasury_tokens,
+            authority,
+            treasury: env.treasury.pubkey(),
+            global_stats: global_stats_pda(&authority),
+            system_program: anchor_lang::system_program::ID,
+            token_program: env.token_program,
+        },
+        program::instruction::InitializeRoundSeries {
+            base_round_id,
+            count,
+            betting_duration: BETTING,
+            fight_duration: FIGHT,
+            initial_hp: HP,
+            prize_pool_amount: POOL,
+            config: RoundConfig {
+                auto_open_claims: true,
+                ..Default::default()
+            },
+        },
+    );
+    for round_id in base_round_id..base_round_id + count as u64 {
+        series.accounts.extend([
+            AccountMeta::new(round_pda(&authority, round_id), false),
+            AccountMeta::new(escrow_pda(&authority, round_id), false),
+        ]);
+    }
+    signed_by(series, &env.treasury.pubkey())
+}
+
+#[tokio::test]
+async fn round_series_creates_independently_funded_rounds() {
+    let mut env = Env::new().await;
+    let series = round_series_ix(&env, 10, 3);
+    env.send_by_authority(&[series]).await.ok();
+
+    for round_id in 10..13 {
+        let round = env.round(round_id).await;
+        assert_eq!(round.round_id, round_id);
+        assert_eq!(round.escrow, env.escrow_key(round_id));
+        assert_eq!(round.betting_end_time, START + BETTING);
+        assert_eq!(env.balance(&round.escrow).await, POOL);
+    }
+    let treasury = env.treasury_tokens;
+    assert_eq!(env.balance(&treasury).await, TREASURY_SUPPLY - 3 * POOL);
+    let stats = env.global_stats().await;
+    assert_eq!(stats.rounds_created, 3);
+    assert_eq!(stats.open_rounds, 3);
+    assert!(env.account(&env.round_key(13)).await.is_none());
+
+    let bettors = env.bettors(11, &[BossPrediction::Death]).await;
+    env.fight_to_end(11, 0).await.ok();
+    env.claim(11, &bettors[0]).await.ok();
+    assert_eq!(env.balance(&bettors[0].tokens).await, POOL);
+    let untouched = env.escrow_key(10);
+    assert_eq!(env.balance(&untouched).await, POOL);
+    assert_eq!(env.round(10).await.total_bets_count, 0);
+
+    let repeat = round_series_ix(&env, 12, 2);
+    env.send_by_authority(&[repeat])
+        .await
+        .expect_err(BettingError::RoundAlreadyExists);
+    let too_many = round_series_ix(&env, 20, MAX_ROUND_SERIES + 1);
+    env.send_by_authority(&[too_many])
+        .await
+        .expect_err(BettingError::InvalidBatchSize);
+}
+
+#[tokio::test]
+async fn game_name_persists_and_tags_the_round_events() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        game_name: String::from("dragon raid"),
+        ..Default::default()
+    };
+    let created = env.create_round(1, config).await.ok();
+    let initialized = created.events::<BettingRoundInitialized>();
+    assert_eq!(initialized[0].game_name, "dragon raid");
+
+    env.bettors(1, &[BossPrediction::Death]).await;
+    let ended = env.fight_to_end(1, 0).await.ok();
+    assert_eq!(ended.events::<FightEnded>()[0].game_name, "dragon raid");
+    assert_eq!(env.round(1).await.config.game_name, "dragon raid");
+
+    for game_name in ["x".repeat(33), String::from("boss\n")] {
+        let config = RoundConfig {
+            game_name,
+            ..Default::default()
+        };
+        env.create_round(2, config)
+            .await
+            .expect_err(BettingError::InvalidGameName);
+    }
+}
+
+fn reclaim_seed_ix(env: &Env, round_id: u64) -> Instruction {
+    ix(
+        program::accounts::ReclaimExcessSeed {
+            betting_round: env.round_key(round_id),
+            escrow_token_account: env.escrow_key(round_id),
+            token_mint: env.mint,
+            treasury_token_account: env.treasury_tokens,
+            authority: env.authority.pubkey(),
+            token_program: env.token_program,
+        },
+        program::instruction::ReclaimExcessSeed {},
+    )
+}
+
+#[tokio::test]
+async fn excess_seed_goes_back_to_the_treasury_only_on_low_turnout() {
+    let mut env = Env::new().await;
+    let full = RoundConfig {
+        expected_bets: 2,
+        auto_open_claims: true,
+        ..Default::default()
+    };
+    let low = RoundConfig {
+        expected_bets: 4,
+        ..full.clone()
+    };
+    env.create_round(1, full).await.ok();
+    env.create_round(2, low).await.ok();
+    env.bettors(1, &[BossPrediction::Death, BossPrediction::Survival])
+        .await;
+    let winners = env.bettors(2, &[BossPrediction::Death]).await;
+    env.warp(START + BETTING).await;
+    env.start_fight(1).await.ok();
+    env.start_fight(2).await.ok();
+    env.warp(START + BETTING + FIGHT).await;
+    env.end_fight(1, 0).await.ok();
+    env.end_fight(2, 0).await.ok();
+
+    let full_turnout = reclaim_seed_ix(&env, 1);
+    env.send_by_authority(&[full_turnout])
+        .await
+        .expect_err(BettingError::NothingToReclaim);
+
+    // One of four expected bets came in, so three quarters of the seed goes back
+    let treasury = env.treasury_tokens;
+    let before = env.balance(&treasury).await;
+    let low_turnout = reclaim_seed_ix(&env, 2);
+    env.send_by_authority(std::slice::from_ref(&low_turnout))
+        .await
+        .ok();
+    assert_eq!(env.balance(&treasury).await, before + 750);
+    assert_eq!(env.round(2).await.outstanding_obligation, 250);
+    env.send_by_authority(&[low_turnout])
+        .await
+        .expect_err(BettingError::SeedAlreadyReclaimed);
+
+    env.claim(2, &winners[0]).await.ok();
+    assert_eq!(env.balance(&winners[0].tokens).await, 250);
+}
+
+fn merge_ix(env: &Env, source: u64, target: u64) -> Instruction {
+    ix(
+        program::accounts::MergeRounds {
+            source_round: env.round_key(source),
+            target_round: env.round_key(target),
+            source_escrow_token_account: env.escrow_key(source),
+            target_escrow_token_account: env.escrow_key(target),
+            token_mint: env.mint,
+            authority: env.authority.pubkey(),
+            token_program: env.token_program,
+        },
+        program::instruction::MergeRounds {},
+    )
+}
+
+#[tokio::test]
+async fn locked_pool_takes_merges_until_the_first_bet_only() {
+    let mut env = Env::new().await;
+    let locking = RoundConfig {
+        lock_pool_after_first_bet: true,
+        ..Default::default()
+    };
+    env.create_round(1, locking).await.ok();
+    for round_id in 2..=5 {
+        env.create_round(round_id, RoundConfig::default())
+            .await
+            .ok();
+    }
+
+    let before_bets = merge_ix(&env, 2, 1);
+    env.send_by_authority(&[before_bets]).await.ok();
+    assert_eq!(env.round(1).await.prize_pool_amount, 2 * POOL);
+
+    env.bettors(1, &[BossPrediction::Death]).await;
+    let after_bet = merge_ix(&env, 3, 1);
+    env.send_by_authority(&[after_bet])
+        .await
+        .expect_err(BettingError::PoolLocked);
+    assert_eq!(env.round(1).await.prize_pool_amount, 2 * POOL);
+
+    // Without the lock a round that already has bets can still grow
+    env.bettors(4, &[BossPrediction::Death]).await;
+    let unlocked = merge_ix(&env, 5, 4);
+    env.send_by_authority(&[unlocked]).await.ok();
+    assert_eq!(env.round(4).await.prize_pool_amount, 2 * POOL);
+}
+
+fn installment_ix(env: &Env, round_id: u64, amount: u64) -> Instruction {
+    ix(
+        program::accounts::AddInstallment {
+            betting_round: env.round_key(round_id),
+            escrow_token_account: env.escrow_key(round_id),
+            token_mint: env.mint,
+            treasury_token_account: env.treasury_tokens,
+            treasury: env.treasury.pubkey(),
+            token_program: env.token_program,
+        },
+        program::instruction::AddInstallment { amount },
+    )
+}
+
+#[tokio::test]
+async fn fight_starts_only_once_installments_reach_the_target() {
+    let mut env = Env::new().await;
+    let config = RoundConfig {
+        target_prize_pool: 2_500,
+        ..Default::default()
+    };
+    env.create_round(1, config.clone()).await.ok();
+    env.create_round(2, config).await.ok();
+    env.create_round(3, RoundConfig::default()).await.ok();
+
+    let first = installment_ix(&env, 1, 1_000);
+    env.send_by_authority(&[first]).await.ok();
+    let short = installment_ix(&env, 2, 1_000);
+    env.send_by_authority(&[short]).await.ok();
+    let disabled = installment_ix(&env, 3, 1_000);
+    env.send_by_authority(&[disabled])
+        .await
+        .expect_err(BettingError::InstallmentsDisabled);
+
+    env.warp(START + BETTING).await;
+    env.start_fight(1)
+        .await
+        .expect_err(BettingError::PoolUnderfunded);
+    let last = installment_ix(&env, 1, 500);
+    env.send_by_authority(&[last]).await.ok();
+    let round = env.round(1).await;
+    assert_eq!(round.prize_pool_amount, 2_500);
+    assert_eq!(env.balance(&round.escrow).await, 2_500);
+    env.start_fight(1).await.ok();
+
+    env.start_fight(2)
+        .await
+        .expect_err(BettingError::PoolUnderfunded);
+    assert_eq!(env.round(2).await.prize_pool_amount, 2_000);
+}
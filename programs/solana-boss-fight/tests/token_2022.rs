@@ -0,0 +1,129 @@
+mod common;
+
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        default_account_state::DefaultAccountState, BaseStateWithExtensionsMut, ExtensionType,
+        StateWithExtensionsMut,
+    },
+    state::{AccountState, Mint},
+};
+use boss_fight_betting::{BettingError, BettingRoundInitialized, BossPrediction, RoundConfig};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Signer,
+};
+
+fn claimable() -> RoundConfig {
+    RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn token_2022_round_funds_escrow_and_pays_out() {
+    let mut env = Env::with_mint(MintKind::Token2022).await;
+    env.create_round(1, claimable()).await.ok();
+    let round = env.round(1).await;
+    assert_eq!(env.balance(&round.escrow).await, POOL);
+
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    env.claim(1, &bettors[0]).await.ok();
+    assert_eq!(env.balance(&bettors[0].tokens).await, POOL);
+    assert_eq!(env.balance(&round.escrow).await, 0);
+}
+
+#[tokio::test]
+async fn transfer_fee_round_pays_out_what_the_escrow_holds() {
+    // 1% on every transfer, rounded up
+    let mut env = Env::with_mint(MintKind::TransferFee(100)).await;
+    env.create_round(1, claimable()).await.ok();
+    let round = env.round(1).await;
+
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+    env.claim(1, &bettors[0]).await.ok();
+    // The winner is paid the whole 990 that landed, less the fee on the way out
+    assert_eq!(env.balance(&bettors[0].tokens).await, 980);
+    assert_eq!(env.balance(&round.escrow).await, 0);
+}
+
+#[tokio::test]
+async fn transfer_fee_round_records_the_pool_that_landed() {
+    let mut env = Env::with_mint(MintKind::TransferFee(100)).await;
+    let created = env.create_round(1, claimable()).await.ok();
+
+    // Sending 1,000 at 1% leaves 990 in escrow, and that is the pool the round promises
+    let round = env.round(1).await;
+    assert_eq!(env.balance(&round.escrow).await, 990);
+    assert_eq!(round.prize_pool_amount, 990);
+    let initialized = created.events::<BettingRoundInitialized>();
+    assert_eq!(initialized[0].prize_pool_amount, 990);
+}
+
+/// Turn `mint` into one whose new token accounts start frozen, so no winner could be paid
+async fn freeze_new_accounts(env: &mut Env, mint: &Pubkey) {
+    let len =
+        ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::DefaultAccountState])
+            .unwrap();
+    let mut data = vec![0; len];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    state
+        .init_extension::<DefaultAccountState>(true)
+        .unwrap()
+        .state = AccountState::Frozen as u8;
+    state.base = Mint {
+        mint_authority: COption::Some(env.ctx.payer.pubkey()),
+        supply: TREASURY_SUPPLY,
+        decimals: DECIMALS,
+        is_initialized: true,
+        freeze_authority: COption::Some(env.ctx.payer.pubkey()),
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+
+    let account = env.account(mint).await.unwrap();
+    let account = Account {
+        lamports: Rent::default().minimum_balance(len),
+        data,
+        ..account
+    };
+    env.ctx.set_account(mint, &AccountSharedData::from(account));
+}
+
+#[tokio::test]
+async fn mint_check_on_bet_rejects_a_mint_that_freezes_new_accounts() {
+    let mut env = Env::with_mint(MintKind::Token2022).await;
+    let config = RoundConfig {
+        validate_mint_on_bet: true,
+        ..Default::default()
+    };
+    env.create_round(1, config).await.ok();
+    let (early, late) = (env.bettor().await, env.bettor().await);
+    let place_with_mint = |env: &Env, bettor: &Bettor| {
+        let mut accounts = env.place_bet_accounts(1, &bettor.pubkey());
+        accounts.token_mint = Some(env.mint);
+        ix(accounts, place_bet_args(BossPrediction::Death))
+    };
+
+    env.bet(1, &early, BossPrediction::Death)
+        .await
+        .expect_err(BettingError::InvalidTokenMint);
+    let place = place_with_mint(&env, &early);
+    env.send(&[place], &[&early.key]).await.ok();
+
+    let mint = env.mint;
+    freeze_new_accounts(&mut env, &mint).await;
+    let place = place_with_mint(&env, &late);
+    env.send(&[place], &[&late.key])
+        .await
+        .expect_err(BettingError::InvalidTokenMint);
+    assert!(env.bet_state(1, &late.pubkey()).await.is_none());
+    assert_eq!(env.round(1).await.total_bets_count, 1);
+}
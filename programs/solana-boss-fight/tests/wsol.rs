@@ -0,0 +1,71 @@
+mod common;
+
+use boss_fight_betting::{self as program, BettingError, BossPrediction, RoundConfig};
+use common::*;
+use solana_program_test::tokio;
+use solana_sdk::instruction::Instruction;
+
+fn claimable() -> RoundConfig {
+    RoundConfig {
+        auto_open_claims: true,
+        ..Default::default()
+    }
+}
+
+fn unwrapping_claim_ix(env: &Env, round_id: u64, bettor: &Bettor) -> Instruction {
+    let claim = ix(
+        env.claim_accounts(round_id, bettor),
+        program::instruction::ClaimPayout { unwrap_sol: true },
+    );
+    signed_by(claim, &bettor.pubkey())
+}
+
+#[tokio::test]
+async fn wsol_winner_can_take_the_payout_as_native_sol() {
+    let mut env = Env::with_mint(MintKind::Native).await;
+    env.create_round(1, claimable()).await.ok();
+    // Someone else funds the bet. Program-test's native CPI only syncs the lamports of
+    // accounts passed to the CPI, so bet rent refunded to the wallet the unwrap pays into
+    // would look unbalanced.
+    let winner = env.bettor().await;
+    let sponsor = env.bettor().await;
+    let mut accounts = env.place_bet_accounts(1, &winner.pubkey());
+    accounts.payer = sponsor.pubkey();
+    let place = ix(accounts, place_bet_args(BossPrediction::Death));
+    env.send(&[place], &[&winner.key, &sponsor.key]).await.ok();
+    env.fight_to_end(1, 0).await.ok();
+
+    let wallet_before = env.lamports(&winner.pubkey()).await;
+    let token_account_rent = env.lamports(&winner.tokens).await;
+    let mut accounts = env.claim_accounts(1, &winner);
+    accounts.rent_payer = sponsor.pubkey();
+    let unsigned = ix(
+        accounts,
+        program::instruction::ClaimPayout { unwrap_sol: true },
+    );
+    env.send(std::slice::from_ref(&unsigned), &[])
+        .await
+        .expect_err(BettingError::UnwrapRequiresBettorSignature);
+
+    let claim = signed_by(unsigned, &winner.pubkey());
+    env.send(&[claim], &[&winner.key]).await.ok();
+    assert!(env.account(&winner.tokens).await.is_none());
+    assert_eq!(
+        env.lamports(&winner.pubkey()).await,
+        wallet_before + POOL + token_account_rent
+    );
+}
+
+#[tokio::test]
+async fn unwrapping_needs_a_wsol_round() {
+    let mut env = Env::new().await;
+    env.create_round(1, claimable()).await.ok();
+    let bettors = env.bettors(1, &[BossPrediction::Death]).await;
+    env.fight_to_end(1, 0).await.ok();
+
+    let claim = unwrapping_claim_ix(&env, 1, &bettors[0]);
+    env.send(&[claim], &[&bettors[0].key])
+        .await
+        .expect_err(BettingError::UnwrapRequiresNativeMint);
+    assert_eq!(env.balance(&bettors[0].tokens).await, 0);
+}